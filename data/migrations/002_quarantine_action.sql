@@ -0,0 +1,30 @@
+-- Allow the 'quarantine' action. SQLite cannot alter a CHECK constraint in
+-- place, so the rules table is rebuilt with the same columns.
+BEGIN TRANSACTION;
+
+CREATE TABLE rules_new (
+  id TEXT PRIMARY KEY,
+  title TEXT NOT NULL,
+  description TEXT NOT NULL,
+  category TEXT NOT NULL,
+  risk TEXT NOT NULL CHECK (risk IN ('low','medium','high')),
+  default_checked INTEGER NOT NULL DEFAULT 0,
+  requires_admin INTEGER NOT NULL DEFAULT 0,
+  rule_type TEXT NOT NULL CHECK (rule_type IN ('path','pattern','special','registry','app_residue')),
+  scope TEXT NOT NULL CHECK (scope IN ('system','user','both')),
+  path TEXT,
+  pattern TEXT,
+  size_threshold_mb INTEGER,
+  age_threshold_days INTEGER,
+  action TEXT NOT NULL CHECK (action IN ('delete','recycle','quarantine','tool_call')),
+  tool_cmd TEXT,
+  enabled INTEGER NOT NULL DEFAULT 1,
+  sort_order INTEGER NOT NULL DEFAULT 0,
+  notes TEXT
+);
+
+INSERT INTO rules_new SELECT * FROM rules;
+DROP TABLE rules;
+ALTER TABLE rules_new RENAME TO rules;
+
+COMMIT;
@@ -0,0 +1,3 @@
+-- Tell user-defined rules apart from seeded ones; seed upgrades leave
+-- 'user' rules alone.
+ALTER TABLE rules ADD COLUMN origin TEXT NOT NULL DEFAULT 'seed' CHECK (origin IN ('seed','user'));
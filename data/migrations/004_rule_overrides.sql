@@ -0,0 +1,8 @@
+-- Track which fields of seed rules the user changed so seed upgrades can
+-- merge new values underneath them.
+CREATE TABLE IF NOT EXISTS rule_overrides (
+  rule_id TEXT NOT NULL,
+  field TEXT NOT NULL,
+  PRIMARY KEY (rule_id, field),
+  FOREIGN KEY (rule_id) REFERENCES rules(id) ON DELETE CASCADE
+);
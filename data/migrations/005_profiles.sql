@@ -0,0 +1,16 @@
+-- Named cleanup profiles: a rule selection plus per-rule threshold overrides.
+CREATE TABLE IF NOT EXISTS profiles (
+  name TEXT PRIMARY KEY,
+  description TEXT NOT NULL DEFAULT '',
+  tags TEXT,
+  sort_order INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS profile_rules (
+  profile TEXT NOT NULL,
+  rule_id TEXT NOT NULL,
+  age_threshold_days INTEGER,
+  size_threshold_mb INTEGER,
+  PRIMARY KEY (profile, rule_id),
+  FOREIGN KEY (profile) REFERENCES profiles(name) ON DELETE CASCADE
+);
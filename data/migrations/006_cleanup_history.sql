@@ -0,0 +1,22 @@
+-- Finished cleanups and the per-rule rows lifetime statistics are built from.
+CREATE TABLE IF NOT EXISTS cleanup_runs (
+  id TEXT PRIMARY KEY,
+  created_at INTEGER NOT NULL,
+  profile TEXT,
+  simulated INTEGER NOT NULL DEFAULT 0,
+  total_bytes INTEGER NOT NULL DEFAULT 0,
+  total_files INTEGER NOT NULL DEFAULT 0,
+  quarantine_run TEXT,
+  report TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS cleanup_run_items (
+  run_id TEXT NOT NULL,
+  rule_id TEXT NOT NULL,
+  category TEXT NOT NULL,
+  status TEXT NOT NULL,
+  total_bytes INTEGER NOT NULL DEFAULT 0,
+  file_count INTEGER NOT NULL DEFAULT 0,
+  PRIMARY KEY (run_id, rule_id),
+  FOREIGN KEY (run_id) REFERENCES cleanup_runs(id) ON DELETE CASCADE
+);
@@ -0,0 +1,3 @@
+-- Bytes of each cleanup item later restored from quarantine, which lifetime
+-- statistics no longer count as reclaimed.
+ALTER TABLE cleanup_run_items ADD COLUMN restored_bytes INTEGER NOT NULL DEFAULT 0;
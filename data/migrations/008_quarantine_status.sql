@@ -0,0 +1,22 @@
+-- Mark quarantine runs whose cleanup finished as 'complete', leaving
+-- 'active' for runs that were interrupted. SQLite cannot alter a CHECK
+-- constraint in place, so the table is rebuilt with the same columns.
+-- Foreign keys are off meanwhile, or dropping the old table would cascade to
+-- quarantine_items.
+PRAGMA foreign_keys = OFF;
+BEGIN TRANSACTION;
+
+CREATE TABLE quarantine_runs_new (
+  id TEXT PRIMARY KEY,
+  created_at INTEGER NOT NULL,
+  status TEXT NOT NULL CHECK (status IN ('active','complete','restored')),
+  total_bytes INTEGER NOT NULL DEFAULT 0,
+  file_count INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT INTO quarantine_runs_new SELECT * FROM quarantine_runs;
+DROP TABLE quarantine_runs;
+ALTER TABLE quarantine_runs_new RENAME TO quarantine_runs;
+
+COMMIT;
+PRAGMA foreign_keys = ON;
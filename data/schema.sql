@@ -13,11 +13,12 @@ CREATE TABLE IF NOT EXISTS rules (
   pattern TEXT,
   size_threshold_mb INTEGER,
   age_threshold_days INTEGER,
-  action TEXT NOT NULL CHECK (action IN ('delete','recycle','tool_call')),
+  action TEXT NOT NULL CHECK (action IN ('delete','recycle','quarantine','tool_call')),
   tool_cmd TEXT,
   enabled INTEGER NOT NULL DEFAULT 1,
   sort_order INTEGER NOT NULL DEFAULT 0,
-  notes TEXT
+  notes TEXT,
+  origin TEXT NOT NULL DEFAULT 'seed' CHECK (origin IN ('seed','user'))
 );
 
 CREATE TABLE IF NOT EXISTS rule_tags (
@@ -36,3 +37,75 @@ CREATE TABLE IF NOT EXISTS settings (
   key TEXT PRIMARY KEY,
   value TEXT NOT NULL
 );
+
+-- Files moved aside by the quarantine action, restorable until purged; a run
+-- still 'active' was interrupted before its cleanup finished
+CREATE TABLE IF NOT EXISTS quarantine_runs (
+  id TEXT PRIMARY KEY,
+  created_at INTEGER NOT NULL,
+  status TEXT NOT NULL CHECK (status IN ('active','complete','restored')),
+  total_bytes INTEGER NOT NULL DEFAULT 0,
+  file_count INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS quarantine_items (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  run_id TEXT NOT NULL,
+  rule_id TEXT NOT NULL,
+  original_path TEXT NOT NULL,
+  stored_path TEXT NOT NULL,
+  size INTEGER NOT NULL,
+  mtime_ns INTEGER,
+  checksum TEXT NOT NULL,
+  restored INTEGER NOT NULL DEFAULT 0,
+  FOREIGN KEY (run_id) REFERENCES quarantine_runs(id) ON DELETE CASCADE
+);
+
+-- Fields of seed rules the user changed; seed upgrades keep these values
+CREATE TABLE IF NOT EXISTS rule_overrides (
+  rule_id TEXT NOT NULL,
+  field TEXT NOT NULL,
+  PRIMARY KEY (rule_id, field),
+  FOREIGN KEY (rule_id) REFERENCES rules(id) ON DELETE CASCADE
+);
+
+-- Saved rule selections; rule_id rows may override thresholds for the run
+CREATE TABLE IF NOT EXISTS profiles (
+  name TEXT PRIMARY KEY,
+  description TEXT NOT NULL DEFAULT '',
+  tags TEXT,
+  sort_order INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS profile_rules (
+  profile TEXT NOT NULL,
+  rule_id TEXT NOT NULL,
+  age_threshold_days INTEGER,
+  size_threshold_mb INTEGER,
+  PRIMARY KEY (profile, rule_id),
+  FOREIGN KEY (profile) REFERENCES profiles(name) ON DELETE CASCADE
+);
+
+-- Finished cleanups; `report` is the full CleanupReport as JSON
+CREATE TABLE IF NOT EXISTS cleanup_runs (
+  id TEXT PRIMARY KEY,
+  created_at INTEGER NOT NULL,
+  profile TEXT,
+  simulated INTEGER NOT NULL DEFAULT 0,
+  total_bytes INTEGER NOT NULL DEFAULT 0,
+  total_files INTEGER NOT NULL DEFAULT 0,
+  quarantine_run TEXT,
+  report TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS cleanup_run_items (
+  run_id TEXT NOT NULL,
+  rule_id TEXT NOT NULL,
+  category TEXT NOT NULL,
+  status TEXT NOT NULL,
+  total_bytes INTEGER NOT NULL DEFAULT 0,
+  file_count INTEGER NOT NULL DEFAULT 0,
+  restored_bytes INTEGER NOT NULL DEFAULT 0,
+  PRIMARY KEY (run_id, rule_id),
+  FOREIGN KEY (run_id) REFERENCES cleanup_runs(id) ON DELETE CASCADE
+);
@@ -1,7 +1,6 @@
 BEGIN TRANSACTION;
 
 INSERT OR REPLACE INTO meta (key, value) VALUES
-  ('schema_version','1'),
   ('seed_version','2');
 
 -- Low risk: temp and caches
@@ -19,7 +18,7 @@ INSERT OR REPLACE INTO rules (id, title, description, category, risk, default_ch
   ('recycle_bin','Recycle Bin','Clear Recycle Bin contents','temp','low',0,0,'special','both',NULL,NULL,NULL,NULL,'tool_call','shell:recycle_bin_empty',1,110,NULL);
 
 -- Browsers (low risk by default, profile-aware)
-INSERT OR REPLACE INTO rules VALUES
+INSERT OR REPLACE INTO rules (id, title, description, category, risk, default_checked, requires_admin, rule_type, scope, path, pattern, size_threshold_mb, age_threshold_days, action, tool_cmd, enabled, sort_order, notes) VALUES
   ('chrome_cache','Chrome cache','Google Chrome cache folders (all profiles)','browser','low',1,0,'pattern','user','%LOCALAPPDATA%\\Google\\Chrome\\User Data','*\\Cache\\**',NULL,NULL,'delete',NULL,1,120,NULL),
   ('chrome_code_cache','Chrome code cache','Chrome code cache folders (all profiles)','browser','low',1,0,'pattern','user','%LOCALAPPDATA%\\Google\\Chrome\\User Data','*\\Code Cache\\**',NULL,NULL,'delete',NULL,1,121,NULL),
   ('chrome_gpu_cache','Chrome GPU cache','Chrome GPU cache folders (all profiles)','browser','low',1,0,'pattern','user','%LOCALAPPDATA%\\Google\\Chrome\\User Data','*\\GPUCache\\**',NULL,NULL,'delete',NULL,1,122,NULL),
@@ -30,7 +29,7 @@ INSERT OR REPLACE INTO rules VALUES
   ('firefox_cache','Firefox cache','Firefox cache2 folders','browser','low',1,0,'pattern','user','%APPDATA%\\Mozilla\\Firefox\\Profiles','*\\cache2\\**',NULL,NULL,'delete',NULL,1,150,NULL);
 
 -- Medium risk: system caches and update caches
-INSERT OR REPLACE INTO rules VALUES
+INSERT OR REPLACE INTO rules (id, title, description, category, risk, default_checked, requires_admin, rule_type, scope, path, pattern, size_threshold_mb, age_threshold_days, action, tool_cmd, enabled, sort_order, notes) VALUES
   ('win_update_cache','Windows Update cache','Downloaded update packages','update','medium',0,1,'path','system','C:\\Windows\\SoftwareDistribution\\Download',NULL,NULL,NULL,'delete',NULL,1,200,NULL),
   ('delivery_opt_cache','Delivery Optimization cache','Update delivery cache','update','medium',0,1,'path','system','%PROGRAMDATA%\\Microsoft\\Windows\\DeliveryOptimization\\Cache',NULL,NULL,NULL,'delete',NULL,1,210,NULL),
   ('windows_update_logs','Windows Update logs','Windows Update logs','logs','medium',0,1,'path','system','%WINDIR%\\Logs\\WindowsUpdate',NULL,NULL,14,'delete',NULL,1,220,NULL),
@@ -43,7 +42,7 @@ INSERT OR REPLACE INTO rules VALUES
   ('onedrive_temp','OneDrive temp','OneDrive temp','apps','medium',0,0,'path','user','%LOCALAPPDATA%\\Microsoft\\OneDrive\\temp',NULL,NULL,NULL,'delete',NULL,1,271,NULL);
 
 -- App caches (medium risk, default off)
-INSERT OR REPLACE INTO rules VALUES
+INSERT OR REPLACE INTO rules (id, title, description, category, risk, default_checked, requires_admin, rule_type, scope, path, pattern, size_threshold_mb, age_threshold_days, action, tool_cmd, enabled, sort_order, notes) VALUES
   ('teams_cache','Teams cache','Microsoft Teams cache','apps','medium',0,0,'path','user','%APPDATA%\\Microsoft\\Teams\\Cache',NULL,NULL,NULL,'delete',NULL,1,300,NULL),
   ('teams_gpu_cache','Teams GPU cache','Microsoft Teams GPU cache','apps','medium',0,0,'path','user','%APPDATA%\\Microsoft\\Teams\\GPUCache',NULL,NULL,NULL,'delete',NULL,1,301,NULL),
   ('office_cache','Office cache','Office file cache','apps','medium',0,0,'path','user','%LOCALAPPDATA%\\Microsoft\\Office\\16.0\\OfficeFileCache',NULL,NULL,NULL,'delete',NULL,1,310,NULL),
@@ -58,7 +57,7 @@ INSERT OR REPLACE INTO rules VALUES
   ('autodesk_cache','Autodesk cache','Autodesk cache folders','apps','medium',0,0,'path','user','%LOCALAPPDATA%\\Autodesk\\Web Services',NULL,NULL,NULL,'delete',NULL,1,390,NULL);
 
 -- High risk: use tool calls or special warnings
-INSERT OR REPLACE INTO rules VALUES
+INSERT OR REPLACE INTO rules (id, title, description, category, risk, default_checked, requires_admin, rule_type, scope, path, pattern, size_threshold_mb, age_threshold_days, action, tool_cmd, enabled, sort_order, notes) VALUES
   ('winsxs_cleanup','WinSxS cleanup (不建议删除该内容，除非您已知删除该内容的风险)','Clean component store via DISM','system','high',0,1,'special','system',NULL,NULL,NULL,NULL,'tool_call','DISM /Online /Cleanup-Image /StartComponentCleanup',1,500,'Use DISM only'),
   ('restore_points','System Restore Points (不建议删除该内容，除非您已知删除该内容的风险)','Delete restore points','system','high',0,1,'special','system',NULL,NULL,NULL,NULL,'tool_call','vssadmin Delete Shadows /All /Quiet',1,510,'Requires admin'),
   ('registry_orphans','Registry orphans (不建议删除该内容，除非您已知删除该内容的风险)','Detect orphan uninstall entries and invalid paths','registry','high',0,1,'registry','system',NULL,NULL,NULL,NULL,'delete',NULL,1,520,'Backup required'),
@@ -0,0 +1,566 @@
+//! Headless front end to the scan and cleanup engine, for build agents and
+//! remote shells.
+//!
+//! Exit codes: 0 success, 1 error, 2 bad usage, 3 finished but some rules
+//! failed, were blocked or were cancelled, 4 needs administrator privileges.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+
+use cdiskcleaner::error::{AppError, AppResult};
+use cdiskcleaner::models::{CleanupReport, Rule, RuleStatus, UnavailableRules};
+use cdiskcleaner::preview::PreviewSort;
+use cdiskcleaner::{
+    cleanup, db, history, jobs, preview, privilege, profiles, protect, quarantine, rules, scan, sources, tags,
+};
+
+const EXIT_ERROR: u8 = 1;
+const EXIT_PARTIAL: u8 = 3;
+const EXIT_REQUIRES_ADMIN: u8 = 4;
+
+/// Tauri's identifier for the desktop app; its data directory is named after it.
+const APP_IDENTIFIER: &str = "com.cdiskcleaner.app";
+
+#[derive(Parser)]
+#[command(name = "cdiskcleaner-cli", version, about = "Scan and clean disk space without the desktop app")]
+struct Cli {
+    /// Directory holding rules.db; defaults to the desktop app's data directory.
+    #[arg(long, global = true)]
+    data_dir: Option<PathBuf>,
+    /// Print JSON instead of text.
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Inspect rules.
+    Rules {
+        #[command(subcommand)]
+        command: RulesCommand,
+    },
+    /// Inspect cleanup profiles.
+    Profiles {
+        #[command(subcommand)]
+        command: ProfilesCommand,
+    },
+    /// Measure what rules would free; every enabled rule unless narrowed.
+    Scan(Selection),
+    /// List the files one rule would touch.
+    Preview {
+        rule: String,
+        #[arg(long, value_enum, default_value_t = Sort::Size)]
+        sort: Sort,
+        #[arg(long, default_value_t = 0)]
+        page: usize,
+        #[arg(long)]
+        page_size: Option<usize>,
+    },
+    /// Clean the selected rules.
+    Clean {
+        #[command(flatten)]
+        selection: Selection,
+        /// Report what would be cleaned without removing anything.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Past cleanup runs and lifetime totals.
+    History {
+        #[command(subcommand)]
+        command: Option<HistoryCommand>,
+    },
+}
+
+#[derive(Subcommand)]
+enum RulesCommand {
+    /// List effective rules.
+    List {
+        /// Include disabled rules.
+        #[arg(long)]
+        all: bool,
+        /// Only rules matching this tag expression, e.g. `browser & !high-risk`.
+        #[arg(long)]
+        tags: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum HistoryCommand {
+    /// List recorded runs, newest first (the default).
+    List {
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Show the full report of one run.
+    Show { run: String },
+    /// Space reclaimed over all runs, by category, rule and month.
+    Stats,
+}
+
+#[derive(Subcommand)]
+enum ProfilesCommand {
+    List,
+}
+
+#[derive(Args)]
+struct Selection {
+    /// Run a saved profile.
+    #[arg(long, conflicts_with_all = ["ids", "tags"])]
+    profile: Option<String>,
+    /// Comma-separated rule ids.
+    #[arg(long, value_delimiter = ',')]
+    ids: Option<Vec<String>>,
+    /// Tag expression; narrows `--ids` when both are given.
+    #[arg(long)]
+    tags: Option<String>,
+}
+
+impl Selection {
+    fn is_empty(&self) -> bool {
+        self.profile.is_none() && self.ids.is_none() && self.tags.is_none()
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Sort {
+    Size,
+    Age,
+    Path,
+}
+
+impl From<Sort> for PreviewSort {
+    fn from(sort: Sort) -> Self {
+        match sort {
+            Sort::Size => PreviewSort::Size,
+            Sort::Age => PreviewSort::Age,
+            Sort::Path => PreviewSort::Path,
+        }
+    }
+}
+
+struct Context {
+    data_dir: PathBuf,
+    db_path: PathBuf,
+    sources: sources::SourceDirs,
+    is_admin: bool,
+    json: bool,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let json = cli.json;
+    match run(cli) {
+        Ok(code) => ExitCode::from(code),
+        Err(err) => {
+            if json {
+                print_json(&serde_json::json!({ "error": err }));
+            } else {
+                eprintln!("error: {}", err);
+            }
+            ExitCode::from(match err {
+                AppError::RequiresAdmin => EXIT_REQUIRES_ADMIN,
+                _ => EXIT_ERROR,
+            })
+        }
+    }
+}
+
+fn run(cli: Cli) -> AppResult<u8> {
+    let data_dir = cli
+        .data_dir
+        .or_else(default_data_dir)
+        .ok_or_else(|| AppError::InvalidInput("Cannot find the data directory; pass --data-dir".to_string()))?;
+    let db_path = db::init_db(&data_dir)?.db_path;
+    let ctx = Context {
+        sources: sources::SourceDirs::new(&data_dir),
+        data_dir,
+        db_path,
+        is_admin: privilege::is_admin(),
+        json: cli.json,
+    };
+    match cli.command {
+        Command::Rules {
+            command: RulesCommand::List { all, tags },
+        } => list_rules(&ctx, all, tags.as_deref()),
+        Command::Profiles {
+            command: ProfilesCommand::List,
+        } => list_profiles(&ctx),
+        Command::Scan(selection) => scan_rules(&ctx, selection),
+        Command::Preview {
+            rule,
+            sort,
+            page,
+            page_size,
+        } => preview_rule(&ctx, &rule, sort.into(), page, page_size),
+        Command::Clean { selection, dry_run } => clean(&ctx, selection, dry_run),
+        Command::History { command } => match command.unwrap_or(HistoryCommand::List { limit: None }) {
+            HistoryCommand::List { limit } => history(&ctx, limit),
+            HistoryCommand::Show { run } => show_run(&ctx, &run),
+            HistoryCommand::Stats => stats(&ctx),
+        },
+    }
+}
+
+fn list_rules(ctx: &Context, all: bool, expr: Option<&str>) -> AppResult<u8> {
+    let conn = db::open_db(&ctx.db_path)?;
+    let mut views = rules::list_rules_with_privilege(&conn, &ctx.sources, ctx.is_admin)?;
+    if let Some(expr) = expr {
+        let loaded: Vec<Rule> = sources::load_rules(&conn, &ctx.sources)?
+            .rules
+            .into_iter()
+            .map(|effective| effective.rule)
+            .collect();
+        let matching = tags::select(&loaded, None, Some(expr))?;
+        views.retain(|view| matching.contains(&view.id));
+    }
+    if !all {
+        views.retain(|view| view.enabled);
+    }
+    if ctx.json {
+        print_json(&views);
+        return Ok(0);
+    }
+    for view in &views {
+        let state = match (view.enabled, &view.invalid_reason, view.blocked) {
+            (false, _, _) => "disabled",
+            (_, Some(_), _) => "invalid",
+            (_, _, true) => "admin",
+            _ => "",
+        };
+        println!(
+            "{:<32} {:<6} {:<8} {:<8} {}",
+            view.id,
+            view.risk.as_str(),
+            view.source.as_str(),
+            state,
+            view.title
+        );
+    }
+    Ok(0)
+}
+
+fn list_profiles(ctx: &Context) -> AppResult<u8> {
+    let conn = db::open_db(&ctx.db_path)?;
+    let profiles = profiles::list_profiles(&conn)?;
+    if ctx.json {
+        print_json(&profiles);
+        return Ok(0);
+    }
+    for profile in &profiles {
+        let mut selection: Vec<String> = profile.rules.iter().map(|entry| entry.rule_id.clone()).collect();
+        if let Some(tags) = &profile.tags {
+            selection.push(format!("[{}]", tags));
+        }
+        println!("{:<16} {}", profile.name, selection.join(", "));
+    }
+    Ok(0)
+}
+
+fn scan_rules(ctx: &Context, selection: Selection) -> AppResult<u8> {
+    let conn = db::open_db(&ctx.db_path)?;
+    let rules = selected_rules(&conn, ctx, selection)?;
+    let options = scan::ScanOptions {
+        is_admin: ctx.is_admin,
+        cancel: jobs::CancelToken::new(),
+    };
+    let results = scan::scan_rules(&rules, &options, &mut |_: &Rule| {});
+    let code = exit_code(results.iter().map(|result| result.status));
+    if ctx.json {
+        print_json(&results);
+        return Ok(code);
+    }
+    for result in &results {
+        println!(
+            "{:<32} {:<12} {:>10} {:>8} files",
+            result.id,
+            result.status.as_str(),
+            format_bytes(result.reclaimable_bytes),
+            result.file_count
+        );
+    }
+    let total: u64 = results.iter().map(|result| result.reclaimable_bytes).sum();
+    println!("Reclaimable: {}", format_bytes(total));
+    Ok(code)
+}
+
+fn preview_rule(
+    ctx: &Context,
+    rule_id: &str,
+    sort: PreviewSort,
+    page: usize,
+    page_size: Option<usize>,
+) -> AppResult<u8> {
+    let conn = db::open_db(&ctx.db_path)?;
+    let rules = sources::enabled_rules(&conn, &ctx.sources)?;
+    let rule = rules
+        .iter()
+        .find(|rule| rule.id == rule_id)
+        .ok_or_else(|| AppError::not_found("rule", rule_id))?;
+    preview::check_rule(rule, ctx.is_admin)?;
+    let options = scan::ScanOptions {
+        is_admin: ctx.is_admin,
+        cancel: jobs::CancelToken::new(),
+    };
+    let preview = preview::preview_rule(rule, &options, sort, page, page_size);
+    let code = exit_code([preview.status]);
+    if ctx.json {
+        print_json(&preview);
+        return Ok(code);
+    }
+    for entry in &preview.entries {
+        println!("{:>10}  {}", format_bytes(entry.size), entry.path.display());
+    }
+    println!(
+        "{} files, {} (page {} of {})",
+        preview.total_files,
+        format_bytes(preview.total_bytes),
+        preview.page + 1,
+        (preview.total_files as usize).div_ceil(preview.page_size).max(1)
+    );
+    if preview.errors.total() > 0 {
+        eprintln!("{} entries could not be read", preview.errors.total());
+    }
+    Ok(code)
+}
+
+fn clean(ctx: &Context, selection: Selection, dry_run: bool) -> AppResult<u8> {
+    if selection.is_empty() {
+        return Err(AppError::InvalidInput(
+            "Choose what to clean with --profile, --ids or --tags".to_string(),
+        ));
+    }
+    let conn = db::open_db(&ctx.db_path)?;
+    let picked = profiles::select_rules(
+        &conn,
+        &ctx.sources,
+        selection.profile.as_deref(),
+        selection.ids,
+        selection.tags.as_deref(),
+    )?;
+    warn_unavailable(&picked.unavailable);
+    let store = quarantine::QuarantineStore {
+        root: ctx.data_dir.join("quarantine"),
+        db_path: ctx.db_path.clone(),
+    };
+    let options = cleanup::CleanupOptions {
+        is_admin: ctx.is_admin,
+        quarantine: Some(store.clone()),
+        protected: protect::ProtectedPaths::load(&conn)?,
+        cancel: jobs::CancelToken::new(),
+        dry_run,
+    };
+    let mut report = cleanup::cleanup_rules(&picked.rules, &picked.selected, &options, &mut |_| {});
+    // Files may already be gone, so the report is still printed.
+    if let Err(err) = history::record_run(&conn, selection.profile.as_deref(), &report) {
+        report.errors.push(format!("Could not record cleanup history: {}", err));
+    }
+    if !dry_run {
+        let _ = quarantine::purge_expired(&conn, &store);
+    }
+    let mut code = exit_code(report.items.iter().map(|item| item.status));
+    if !report.errors.is_empty() {
+        code = EXIT_PARTIAL;
+    }
+    if ctx.json {
+        print_json(&report);
+        return Ok(code);
+    }
+    print_report(&report);
+    Ok(code)
+}
+
+fn history(ctx: &Context, limit: Option<usize>) -> AppResult<u8> {
+    let conn = db::open_db(&ctx.db_path)?;
+    let runs = history::list_runs(&conn, limit)?;
+    if ctx.json {
+        print_json(&runs);
+        return Ok(0);
+    }
+    for run in &runs {
+        println!(
+            "{}  {:<28} {:<12} {:>10} {:>8} files{}",
+            format_time(run.created_at),
+            run.id,
+            run.profile.as_deref().unwrap_or("-"),
+            format_bytes(run.total_bytes),
+            run.total_files,
+            if run.simulated { "  (dry run)" } else { "" }
+        );
+    }
+    Ok(0)
+}
+
+fn show_run(ctx: &Context, run_id: &str) -> AppResult<u8> {
+    let conn = db::open_db(&ctx.db_path)?;
+    let detail = history::get_run(&conn, run_id)?;
+    if ctx.json {
+        print_json(&detail);
+        return Ok(0);
+    }
+    println!(
+        "{}  {}{}",
+        format_time(detail.run.created_at),
+        detail.run.id,
+        if detail.run.simulated { " (dry run)" } else { "" }
+    );
+    print_report(&detail.report);
+    Ok(0)
+}
+
+fn stats(ctx: &Context) -> AppResult<u8> {
+    let conn = db::open_db(&ctx.db_path)?;
+    let stats = history::lifetime_stats(&conn)?;
+    if ctx.json {
+        print_json(&stats);
+        return Ok(0);
+    }
+    println!(
+        "Reclaimed {} in {} files over {} runs",
+        format_bytes(stats.total_bytes),
+        stats.total_files,
+        stats.runs
+    );
+    if stats.quarantined_bytes > 0 {
+        println!("{} still in quarantine", format_bytes(stats.quarantined_bytes));
+    }
+    for (title, buckets) in [
+        ("By category", &stats.by_category),
+        ("By rule", &stats.by_rule),
+        ("By month", &stats.by_month),
+    ] {
+        println!("\n{}", title);
+        for bucket in buckets {
+            println!("  {:<32} {:>10} {:>5.1}%", bucket.key, format_bytes(bucket.bytes), bucket.percent);
+        }
+    }
+    Ok(0)
+}
+
+/// Per-rule lines plus totals, as printed by `clean` and `history show`.
+fn print_report(report: &CleanupReport) {
+    for item in &report.items {
+        println!(
+            "{:<32} {:<12} {:>10} {:>8} files{}",
+            item.id,
+            item.status.as_str(),
+            format_bytes(item.total_bytes),
+            item.file_count,
+            item.message.as_deref().map(|message| format!("  {}", message)).unwrap_or_default()
+        );
+    }
+    println!(
+        "{} {} files, {}",
+        if report.simulated { "Would clean" } else { "Cleaned" },
+        report.summary.total_files,
+        format_bytes(report.summary.total_bytes)
+    );
+    if let Some(run) = &report.quarantine_run {
+        println!("Quarantined as run {}", run);
+    }
+    for error in &report.errors {
+        eprintln!("error: {}", error);
+    }
+}
+
+/// Enabled rules narrowed by `selection`, or all of them if it is empty.
+fn selected_rules(conn: &rusqlite::Connection, ctx: &Context, selection: Selection) -> AppResult<Vec<Rule>> {
+    if selection.is_empty() {
+        return sources::enabled_rules(conn, &ctx.sources);
+    }
+    let picked = profiles::select_rules(
+        conn,
+        &ctx.sources,
+        selection.profile.as_deref(),
+        selection.ids,
+        selection.tags.as_deref(),
+    )?;
+    warn_unavailable(&picked.unavailable);
+    let mut rules = picked.rules;
+    rules.retain(|rule| picked.selected.contains(&rule.id));
+    Ok(rules)
+}
+
+/// Profile rules that will not run; stderr, so `--json` output stays clean.
+fn warn_unavailable(unavailable: &UnavailableRules) {
+    if !unavailable.unknown.is_empty() {
+        eprintln!("warning: profile lists unknown rules: {}", unavailable.unknown.join(", "));
+    }
+    if !unavailable.disabled.is_empty() {
+        eprintln!("warning: profile lists disabled rules: {}", unavailable.disabled.join(", "));
+    }
+}
+
+/// A missing path or a skipped rule is not a failure; anything else short
+/// of `ok` is.
+fn exit_code(statuses: impl IntoIterator<Item = RuleStatus>) -> u8 {
+    let failed = statuses.into_iter().any(|status| {
+        !matches!(
+            status,
+            RuleStatus::Ok | RuleStatus::Missing | RuleStatus::MissingPath | RuleStatus::Skipped
+        )
+    });
+    if failed {
+        EXIT_PARTIAL
+    } else {
+        0
+    }
+}
+
+/// Where the desktop app keeps its data (Tauri's app data directory).
+fn default_data_dir() -> Option<PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| Path::new(&home).join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+    };
+    base.map(|base| base.join(APP_IDENTIFIER))
+}
+
+fn print_json<T: Serialize>(value: &T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(text) => println!("{}", text),
+        Err(err) => eprintln!("error: {}", err),
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// `YYYY-MM-DD HH:MM` in UTC for Unix seconds.
+fn format_time(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let minutes = (secs % 86_400) / 60;
+    // Civil-from-days, after Howard Hinnant's date algorithms.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, minutes / 60, minutes % 60)
+}
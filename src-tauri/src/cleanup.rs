@@ -1,76 +1,320 @@
 use std::collections::HashMap;
-use std::env;
-use std::path::{Path, PathBuf};
+use std::fs::Metadata;
+use std::io;
+use std::path::Path;
 use std::process::Command;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant};
 
-use glob::Pattern;
-use jwalk::WalkDir;
+use crate::models::{
+    Action, CleanupItemReport, CleanupPlan, CleanupProgress, CleanupReport, CleanupSummary, PlanEntry, PlanRule,
+    Refusals, Rule, RuleStatus, RuleType, SummaryBucket, WalkErrors,
+};
+use crate::jobs::CancelToken;
+use crate::plan;
+use crate::protect::{ProtectedPaths, Refusal};
+use crate::quarantine::{QuarantineRun, QuarantineStore};
+use crate::scan::ScanOptions;
 
-use crate::models::{CleanupItemReport, CleanupReport, CleanupSummary, Rule, SummaryBucket};
+/// Minimum time between two `cleanup:progress` updates.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
 
 pub struct CleanupOptions {
     pub is_admin: bool,
+    /// Store used by rules whose action is `quarantine`.
+    pub quarantine: Option<QuarantineStore>,
+    /// Checked before every file or directory is removed.
+    pub protected: ProtectedPaths,
+    pub cancel: CancelToken,
+    /// Runs every check but skips the destructive call; the report is marked
+    /// `simulated`.
+    pub dry_run: bool,
 }
 
-pub fn cleanup_rules(rules: &[Rule], selected_ids: &[String], options: &CleanupOptions) -> CleanupReport {
-    let mut items: Vec<CleanupItemReport> = Vec::new();
+/// Plans and immediately runs a cleanup for `selected_ids`.
+pub fn cleanup_rules<F>(
+    rules: &[Rule],
+    selected_ids: &[String],
+    options: &CleanupOptions,
+    progress: &mut F,
+) -> CleanupReport
+where
+    F: FnMut(&CleanupProgress),
+{
+    let scan_options = ScanOptions {
+        is_admin: options.is_admin,
+        cancel: options.cancel.clone(),
+    };
+    let plan = plan::build_plan(rules, selected_ids, &scan_options);
+    execute_plan(&plan, options, progress)
+}
 
-    for rule in rules.iter().filter(|rule| selected_ids.contains(&rule.id)) {
+/// Deletes exactly the entries recorded in `plan`, minus its exclusions.
+///
+/// Stops between files once cancellation is requested; the rule in flight and
+/// every rule after it are reported as `cancelled`.
+pub fn execute_plan<F>(plan: &CleanupPlan, options: &CleanupOptions, progress: &mut F) -> CleanupReport
+where
+    F: FnMut(&CleanupProgress),
+{
+    let mut items: Vec<CleanupItemReport> = Vec::new();
+    let mut errors: Vec<String> = options
+        .protected
+        .invalid()
+        .iter()
+        .map(|entry| format!("Protected path {} was ignored: {}", entry.entry, entry.message))
+        .collect();
+    let mut remover = Remover::new(options);
+    let (total_bytes, total_files) = plan
+        .rules
+        .iter()
+        .flat_map(|planned| planned.entries.iter())
+        .filter(|entry| !plan::is_excluded(plan, &entry.path))
+        .fold((0u64, 0u64), |(bytes, files), entry| (bytes + entry.size, files + 1));
+    let mut progress = Progress::new(progress, total_bytes, total_files);
+
+    for planned in &plan.rules {
+        let rule = &planned.rule;
+        if options.cancel.is_cancelled() {
+            let mut report = base_report(rule);
+            report.status = RuleStatus::Cancelled;
+            items.push(report);
+            continue;
+        }
+        progress.start_rule(rule);
         if rule.requires_admin && !options.is_admin {
             items.push(CleanupItemReport {
                 id: rule.id.clone(),
                 title: rule.title.clone(),
                 category: rule.category.clone(),
-                risk: rule.risk.clone(),
+                risk: rule.risk,
                 total_bytes: 0,
                 file_count: 0,
-                status: "blocked".to_string(),
+                skipped_changed: 0,
+                status: RuleStatus::Blocked,
                 message: Some("Requires administrator privileges".to_string()),
                 drive: None,
+                errors: WalkErrors::default(),
+                refused: Refusals::default(),
             });
             continue;
         }
+        if planned.roots.iter().any(|root| options.protected.covers(root)) {
+            let mut report = base_report(rule);
+            report.status = RuleStatus::Protected;
+            report.message = Some("Rule targets a protected path".to_string());
+            items.push(report);
+            continue;
+        }
+        if planned.status != RuleStatus::Ok {
+            let mut report = base_report(rule);
+            report.status = planned.status;
+            report.message = planned.message.clone();
+            report.errors = planned.errors.clone();
+            items.push(report);
+            continue;
+        }
 
-        let report = match rule.rule_type.as_str() {
-            "path" | "pattern" => cleanup_path_rule(rule),
-            "special" => cleanup_tool_rule(rule),
-            "registry" => cleanup_registry_rule(rule),
-            "app_residue" => cleanup_residue_rule(rule),
-            _ => CleanupItemReport {
-                id: rule.id.clone(),
-                title: rule.title.clone(),
-                category: rule.category.clone(),
-                risk: rule.risk.clone(),
-                total_bytes: 0,
-                file_count: 0,
-                status: "unknown".to_string(),
-                message: Some("Unknown rule type".to_string()),
-                drive: None,
-            },
+        let report = match rule.rule_type {
+            RuleType::Path | RuleType::Pattern => cleanup_path_rule(plan, planned, &mut remover, &mut progress),
+            RuleType::Special => cleanup_tool_rule(rule, options.dry_run),
+            RuleType::Registry => cleanup_registry_rule(planned, options.dry_run),
+            RuleType::AppResidue => cleanup_residue_rule(plan, planned, &mut remover, &mut progress),
         };
         items.push(report);
     }
 
+    let quarantine_run = remover.finish(&mut errors);
     let summary = summarize(&items);
-    CleanupReport { items, summary }
+    CleanupReport {
+        items,
+        summary,
+        quarantine_run,
+        simulated: options.dry_run,
+        errors,
+    }
+}
+
+/// Throttles `cleanup:progress` updates to one per `PROGRESS_INTERVAL`.
+struct Progress<'a> {
+    emit: &'a mut dyn FnMut(&CleanupProgress),
+    last_emit: Option<Instant>,
+    current: CleanupProgress,
+}
+
+impl<'a> Progress<'a> {
+    fn new(emit: &'a mut dyn FnMut(&CleanupProgress), total_bytes: u64, total_files: u64) -> Self {
+        Progress {
+            emit,
+            last_emit: None,
+            current: CleanupProgress {
+                id: String::new(),
+                title: String::new(),
+                files: 0,
+                bytes: 0,
+                total_files,
+                total_bytes,
+                path: None,
+            },
+        }
+    }
+
+    fn start_rule(&mut self, rule: &Rule) {
+        self.current.id = rule.id.clone();
+        self.current.title = rule.title.clone();
+        self.current.path = None;
+        self.flush();
+    }
+
+    fn file(&mut self, entry: &PlanEntry) {
+        self.current.files += 1;
+        self.current.bytes += entry.size;
+        let due = self
+            .last_emit
+            .map(|last| last.elapsed() >= PROGRESS_INTERVAL)
+            .unwrap_or(true);
+        if due {
+            self.current.path = Some(entry.path.to_string_lossy().into_owned());
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        (self.emit)(&self.current);
+        self.last_emit = Some(Instant::now());
+    }
+}
+
+/// Performs the destructive step for a rule's action.
+struct Remover<'a> {
+    options: &'a CleanupOptions,
+    run: Option<QuarantineRun>,
+}
+
+impl<'a> Remover<'a> {
+    fn new(options: &'a CleanupOptions) -> Self {
+        Remover { options, run: None }
+    }
+
+    /// Every removal goes through here; `Err` carries a `Refusal` when the
+    /// protected-path check stopped it.
+    fn remove(&mut self, rule_id: &str, action: Action, path: &Path, meta: &Metadata) -> io::Result<()> {
+        self.guard(path)?;
+        if self.options.dry_run {
+            return self.simulate(action);
+        }
+        match action {
+            Action::Recycle => trash::delete(path).map_err(io::Error::other),
+            Action::Quarantine => {
+                if self.run.is_none() {
+                    self.run = Some(QuarantineRun::begin(self.store()?)?);
+                }
+                match self.run.as_mut() {
+                    Some(run) => run.stash(rule_id, path, meta),
+                    None => Ok(()),
+                }
+            }
+            Action::Delete => std::fs::remove_file(path),
+            Action::ToolCall => Err(io::Error::new(io::ErrorKind::Unsupported, "Tool rules do not remove files")),
+        }
+    }
+
+    fn remove_dir(&mut self, dir: &Path) -> io::Result<()> {
+        self.guard(dir)?;
+        if self.options.dry_run {
+            return Ok(());
+        }
+        std::fs::remove_dir(dir)
+    }
+
+    /// Fails a dry run wherever the real call would fail before touching disk.
+    fn simulate(&self, action: Action) -> io::Result<()> {
+        match action {
+            Action::Quarantine => self.store().map(|_| ()),
+            Action::ToolCall => Err(io::Error::new(io::ErrorKind::Unsupported, "Tool rules do not remove files")),
+            Action::Delete | Action::Recycle => Ok(()),
+        }
+    }
+
+    fn store(&self) -> io::Result<&'a QuarantineStore> {
+        self.options
+            .quarantine
+            .as_ref()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "Quarantine store not configured"))
+    }
+
+    fn guard(&self, path: &Path) -> io::Result<()> {
+        self.options
+            .protected
+            .check(path)
+            .map_err(|refusal| io::Error::new(io::ErrorKind::PermissionDenied, refusal))
+    }
+
+    /// Returns the quarantine run id even if its totals could not be saved,
+    /// so the stashed files can still be found and restored.
+    fn finish(self, errors: &mut Vec<String>) -> Option<String> {
+        let run = self.run?;
+        let id = run.id.clone();
+        if let Err(err) = run.finish() {
+            errors.push(format!("Could not finish quarantine run {}: {}", id, err));
+        }
+        Some(id)
+    }
+}
+
+#[derive(Default)]
+struct Tally {
+    total_bytes: u64,
+    file_count: u64,
+    skipped_changed: u64,
+    errors: WalkErrors,
+    refused: Refusals,
+    cancelled: bool,
+}
+
+impl Tally {
+    fn record_failure(&mut self, path: &Path, err: &io::Error) {
+        match refusal(err) {
+            Some(refusal) => self.refused.record(path, refusal.reason()),
+            None => self.errors.record(path, err),
+        }
+    }
+
+    fn apply(self, report: &mut CleanupItemReport) {
+        report.total_bytes = self.total_bytes;
+        report.file_count = self.file_count;
+        report.skipped_changed = self.skipped_changed;
+        report.status = if self.cancelled {
+            RuleStatus::Cancelled
+        } else if self.errors.total() > 0 || self.refused.count > 0 {
+            RuleStatus::Partial
+        } else {
+            RuleStatus::Ok
+        };
+        report.errors = self.errors;
+        report.refused = self.refused;
+    }
 }
 
-fn cleanup_tool_rule(rule: &Rule) -> CleanupItemReport {
+fn cleanup_tool_rule(rule: &Rule, dry_run: bool) -> CleanupItemReport {
     let mut report = base_report(rule);
-    if rule.action != "tool_call" {
-        report.status = "skipped".to_string();
+    if rule.action != Action::ToolCall {
+        report.status = RuleStatus::Skipped;
         report.message = Some("Action not supported for special rule".to_string());
         return report;
     }
     let cmd = match &rule.tool_cmd {
         Some(cmd) => cmd,
         None => {
-            report.status = "error".to_string();
+            report.status = RuleStatus::Error;
             report.message = Some("Missing tool command".to_string());
             return report;
         }
     };
+    if dry_run {
+        report.status = RuleStatus::Ok;
+        report.message = Some(format!("Would run: {}", cmd));
+        return report;
+    }
     let status = if cfg!(target_os = "windows") {
         Command::new("cmd").args(["/C", cmd]).status()
     } else {
@@ -78,213 +322,144 @@ fn cleanup_tool_rule(rule: &Rule) -> CleanupItemReport {
     };
     match status {
         Ok(exit) if exit.success() => {
-            report.status = "ok".to_string();
+            report.status = RuleStatus::Ok;
         }
         Ok(exit) => {
-            report.status = "error".to_string();
+            report.status = RuleStatus::Error;
             report.message = Some(format!("Tool exit code: {:?}", exit.code()));
         }
         Err(err) => {
-            report.status = "error".to_string();
+            report.status = RuleStatus::Error;
             report.message = Some(err.to_string());
         }
     }
     report
 }
 
-fn cleanup_path_rule(rule: &Rule) -> CleanupItemReport {
-    let mut report = base_report(rule);
-    let base = match &rule.path {
-        Some(path) => expand_percent_env(path),
-        None => {
-            report.status = "missing_path".to_string();
-            return report;
-        }
-    };
-    let base_path = PathBuf::from(&base);
-    if !base_path.exists() {
-        report.status = "missing".to_string();
-        return report;
-    }
-    report.drive = drive_from_path(&base_path);
-
-    let now = SystemTime::now();
-    let age_threshold = rule
-        .age_threshold_days
-        .and_then(|days| days.try_into().ok())
-        .map(|days: u64| Duration::from_secs(days * 24 * 60 * 60));
-    let size_threshold = rule
-        .size_threshold_mb
-        .and_then(|mb| mb.try_into().ok())
-        .map(|mb: u64| mb * 1024 * 1024);
-
-    let pattern = rule.pattern.as_deref().map(normalize_pattern);
-    let matcher = pattern
-        .as_deref()
-        .and_then(|p| Pattern::new(p).ok());
+fn cleanup_path_rule(
+    plan: &CleanupPlan,
+    planned: &PlanRule,
+    remover: &mut Remover,
+    progress: &mut Progress,
+) -> CleanupItemReport {
+    let mut report = base_report(&planned.rule);
+    report.drive = planned.roots.first().and_then(|root| drive_from_path(root));
+
+    let tally = process_entries(plan, planned, remover, progress);
+    tally.apply(&mut report);
+    report
+}
 
-    let mut total_bytes: u64 = 0;
-    let mut file_count: u64 = 0;
-    let mut had_error = false;
-
-    if base_path.is_file() {
-        process_path(
-            &base_path,
-            &base_path,
-            &matcher,
-            now,
-            age_threshold,
-            size_threshold,
-            rule.action.as_str(),
-            &mut total_bytes,
-            &mut file_count,
-            &mut had_error,
-        );
-    } else {
-        for entry in WalkDir::new(&base_path)
-            .follow_links(false)
-            .into_iter()
-        {
-            let entry = match entry {
-                Ok(entry) => entry,
-                Err(_) => {
-                    had_error = true;
-                    continue;
-                }
-            };
-            if !entry.file_type().is_file() {
-                continue;
-            }
-            let path = entry.path();
-            process_path(
-                &path,
-                &base_path,
-                &matcher,
-                now,
-                age_threshold,
-                size_threshold,
-                rule.action.as_str(),
-                &mut total_bytes,
-                &mut file_count,
-                &mut had_error,
-            );
+/// Files the plan walk could not read count against the rule as well.
+fn process_entries(plan: &CleanupPlan, planned: &PlanRule, remover: &mut Remover, progress: &mut Progress) -> Tally {
+    let mut tally = Tally {
+        errors: planned.errors.clone(),
+        ..Tally::default()
+    };
+    for entry in &planned.entries {
+        if remover.options.cancel.is_cancelled() {
+            tally.cancelled = true;
+            break;
+        }
+        if plan::is_excluded(plan, &entry.path) {
+            continue;
         }
+        process_path(&planned.rule, entry, remover, &mut tally);
+        progress.file(entry);
     }
-
-    report.total_bytes = total_bytes;
-    report.file_count = file_count;
-    report.status = if had_error { "partial".to_string() } else { "ok".to_string() };
-    report
+    tally
 }
 
-fn cleanup_registry_rule(rule: &Rule) -> CleanupItemReport {
-    let mut report = base_report(rule);
+fn cleanup_registry_rule(planned: &PlanRule, dry_run: bool) -> CleanupItemReport {
+    let mut report = base_report(&planned.rule);
     if !cfg!(target_os = "windows") {
-        report.status = "unsupported".to_string();
+        report.status = RuleStatus::Unsupported;
         report.message = Some("Registry cleanup only supported on Windows".to_string());
         return report;
     }
-    let entries = registry_orphans();
     let mut cleaned = 0u64;
-    let mut had_error = false;
-    for entry in entries {
-        if let Err(err) = delete_registry_key(&entry) {
-            had_error = true;
-            report.message = Some(err);
-        } else {
-            cleaned += 1;
+    for entry in &planned.registry_keys {
+        let result = if dry_run { Ok(()) } else { delete_registry_key(entry) };
+        match result {
+            Ok(()) => cleaned += 1,
+            Err(err) => report.errors.record(Path::new(entry), &err),
         }
     }
     report.file_count = cleaned;
-    report.status = if had_error { "partial".to_string() } else { "ok".to_string() };
-    report.message = Some("Only orphan uninstall keys with missing InstallLocation were removed. Portable apps may be misdetected; review carefully.".to_string());
+    report.status = if report.errors.total() > 0 { RuleStatus::Partial } else { RuleStatus::Ok };
+    report.message = Some(if dry_run {
+        format!("{} orphan uninstall keys with missing InstallLocation would be removed. Portable apps may be misdetected; review carefully.", cleaned)
+    } else {
+        "Only orphan uninstall keys with missing InstallLocation were removed. Portable apps may be misdetected; review carefully.".to_string()
+    });
     report
 }
 
-fn cleanup_residue_rule(rule: &Rule) -> CleanupItemReport {
-    let mut report = base_report(rule);
-    let candidates = residue_candidates(rule.age_threshold_days);
-    let mut total_bytes: u64 = 0;
-    let mut file_count: u64 = 0;
-    let mut had_error = false;
-    for dir in candidates {
-        let (bytes, files) = scan_directory(&dir, SystemTime::now(), None, None, None);
-        total_bytes += bytes;
-        file_count += files;
-        if let Err(_) = std::fs::remove_dir_all(&dir) {
-            had_error = true;
-        }
+fn cleanup_residue_rule(
+    plan: &CleanupPlan,
+    planned: &PlanRule,
+    remover: &mut Remover,
+    progress: &mut Progress,
+) -> CleanupItemReport {
+    let mut report = base_report(&planned.rule);
+    let mut tally = process_entries(plan, planned, remover, progress);
+    for dir in &planned.roots {
+        prune_empty_dirs(plan, dir, remover, &mut tally);
+    }
+    tally.apply(&mut report);
+    report.message = Some(if remover.options.dry_run {
+        "Would remove old folders not linked to uninstall records. Portable apps may be misdetected; review carefully."
+    } else {
+        "Removed old folders not linked to uninstall records. Portable apps may be misdetected; review carefully."
     }
-    report.total_bytes = total_bytes;
-    report.file_count = file_count;
-    report.status = if had_error { "partial".to_string() } else { "ok".to_string() };
-    report.message = Some("Removed old folders not linked to uninstall records. Portable apps may be misdetected; review carefully.".to_string());
+    .to_string());
     report
 }
 
-fn process_path(
-    path: &Path,
-    base_path: &Path,
-    matcher: &Option<Pattern>,
-    now: SystemTime,
-    age_threshold: Option<Duration>,
-    size_threshold: Option<u64>,
-    action: &str,
-    total_bytes: &mut u64,
-    file_count: &mut u64,
-    had_error: &mut bool,
-) {
-    if let Some(matcher) = &matcher {
-        if let Some(rel) = path.strip_prefix(&base_path).ok() {
-            let rel_norm = normalize_path(rel);
-            if !matcher.matches(&rel_norm) {
-                return;
-            }
-        }
-    }
-    let meta = match path.metadata() {
+/// Removes one planned file, skipping it if it changed since the plan was built.
+fn process_path(rule: &Rule, entry: &PlanEntry, remover: &mut Remover, tally: &mut Tally) {
+    let meta = match entry.path.metadata() {
         Ok(meta) => meta,
-        Err(_) => {
-            *had_error = true;
+        Err(err) => {
+            tally.errors.record(&entry.path, &err);
             return;
         }
     };
-    if !should_count(&meta, now, age_threshold, size_threshold) {
+    if meta.len() != entry.size || plan::mtime_ns(&meta) != entry.mtime_ns {
+        tally.skipped_changed += 1;
         return;
     }
-    *total_bytes += meta.len();
-    *file_count += 1;
-
-    if action == "recycle" {
-        if let Err(_) = trash::delete(&path) {
-            *had_error = true;
+    match remover.remove(&rule.id, rule.action, &entry.path, &meta) {
+        Ok(()) => {
+            tally.total_bytes += meta.len();
+            tally.file_count += 1;
         }
-    } else if let Err(_) = std::fs::remove_file(&path) {
-        *had_error = true;
+        Err(err) => tally.record_failure(&entry.path, &err),
     }
 }
 
-fn should_count(
-    meta: &std::fs::Metadata,
-    now: SystemTime,
-    age_threshold: Option<Duration>,
-    size_threshold: Option<u64>,
-) -> bool {
-    if let Some(min_size) = size_threshold {
-        if meta.len() < min_size {
-            return false;
-        }
+/// Removes `dir` and any subdirectories left empty after their files were deleted.
+fn prune_empty_dirs(plan: &CleanupPlan, dir: &Path, remover: &mut Remover, tally: &mut Tally) {
+    if plan::is_excluded(plan, dir) {
+        return;
     }
-    if let Some(min_age) = age_threshold {
-        if let Ok(modified) = meta.modified() {
-            if let Ok(age) = now.duration_since(modified) {
-                if age < min_age {
-                    return false;
-                }
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+                prune_empty_dirs(plan, &entry.path(), remover, tally);
             }
         }
     }
-    true
+    // Non-empty directories are expected to fail; only refusals are reported.
+    if let Err(err) = remover.remove_dir(dir) {
+        if refusal(&err).is_some() {
+            tally.record_failure(dir, &err);
+        }
+    }
+}
+
+fn refusal(err: &io::Error) -> Option<&Refusal> {
+    err.get_ref().and_then(|inner| inner.downcast_ref::<Refusal>())
 }
 
 fn base_report(rule: &Rule) -> CleanupItemReport {
@@ -292,12 +467,15 @@ fn base_report(rule: &Rule) -> CleanupItemReport {
         id: rule.id.clone(),
         title: rule.title.clone(),
         category: rule.category.clone(),
-        risk: rule.risk.clone(),
+        risk: rule.risk,
         total_bytes: 0,
         file_count: 0,
-        status: "pending".to_string(),
+        skipped_changed: 0,
+        status: RuleStatus::Pending,
         message: None,
         drive: None,
+        errors: WalkErrors::default(),
+        refused: Refusals::default(),
     }
 }
 
@@ -308,7 +486,7 @@ fn summarize(items: &[CleanupItemReport]) -> CleanupSummary {
     let mut by_drive: HashMap<String, (u64, u64)> = HashMap::new();
 
     for item in items {
-        if item.status != "ok" && item.status != "partial" {
+        if !matches!(item.status, RuleStatus::Ok | RuleStatus::Partial | RuleStatus::Cancelled) {
             continue;
         }
         total_bytes += item.total_bytes;
@@ -350,49 +528,10 @@ fn buckets_from_map(map: HashMap<String, (u64, u64)>, total: u64) -> Vec<Summary
             },
         })
         .collect();
-    buckets.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+    buckets.sort_by_key(|b| std::cmp::Reverse(b.bytes));
     buckets
 }
 
-fn expand_percent_env(input: &str) -> String {
-    let mut out = String::with_capacity(input.len());
-    let mut chars = input.chars().peekable();
-    while let Some(ch) = chars.next() {
-        if ch == '%' {
-            let mut var = String::new();
-            while let Some(&next) = chars.peek() {
-                chars.next();
-                if next == '%' {
-                    break;
-                }
-                var.push(next);
-            }
-            if var.is_empty() {
-                out.push('%');
-                continue;
-            }
-            if let Ok(val) = env::var(&var) {
-                out.push_str(&val);
-            } else {
-                out.push('%');
-                out.push_str(&var);
-                out.push('%');
-            }
-        } else {
-            out.push(ch);
-        }
-    }
-    out
-}
-
-fn normalize_path(path: &Path) -> String {
-    path.to_string_lossy().replace('\\', "/")
-}
-
-fn normalize_pattern(pattern: &str) -> String {
-    pattern.replace('\\', "/")
-}
-
 fn drive_from_path(path: &Path) -> Option<String> {
     let text = path.to_string_lossy();
     let mut chars = text.chars();
@@ -405,7 +544,7 @@ fn drive_from_path(path: &Path) -> Option<String> {
 }
 
 #[cfg(target_os = "windows")]
-fn registry_orphans() -> Vec<String> {
+pub fn registry_orphans() -> Vec<String> {
     use winreg::enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, KEY_READ};
     use winreg::RegKey;
 
@@ -441,12 +580,12 @@ fn registry_orphans() -> Vec<String> {
 }
 
 #[cfg(not(target_os = "windows"))]
-fn registry_orphans() -> Vec<String> {
+pub fn registry_orphans() -> Vec<String> {
     Vec::new()
 }
 
 #[cfg(target_os = "windows")]
-fn delete_registry_key(subkey: &str) -> Result<(), String> {
+fn delete_registry_key(subkey: &str) -> io::Result<()> {
     use winreg::enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE};
     use winreg::RegKey;
 
@@ -458,128 +597,10 @@ fn delete_registry_key(subkey: &str) -> Result<(), String> {
         (HKEY_CURRENT_USER, subkey)
     };
     let root = RegKey::predef(hkey);
-    root.delete_subkey_all(path).map_err(|e| e.to_string())
-}
-
-#[cfg(not(target_os = "windows"))]
-fn delete_registry_key(_subkey: &str) -> Result<(), String> {
-    Err("Not supported".to_string())
-}
-
-#[cfg(target_os = "windows")]
-fn residue_candidates(age_threshold_days: Option<i64>) -> Vec<PathBuf> {
-    use winreg::enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, KEY_READ};
-    use winreg::RegKey;
-
-    let mut roots: Vec<PathBuf> = Vec::new();
-    let env_keys = [
-        "ProgramFiles",
-        "ProgramFiles(x86)",
-        "ProgramData",
-        "LOCALAPPDATA",
-        "APPDATA",
-    ];
-    for key in env_keys {
-        if let Ok(val) = env::var(key) {
-            roots.push(PathBuf::from(val));
-        }
-    }
-
-    let mut installed = Vec::new();
-    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
-    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-    let keys = [
-        "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall",
-        "SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall",
-    ];
-    for root in [hklm, hkcu] {
-        for key in keys {
-            if let Ok(uninstall) = root.open_subkey_with_flags(key, KEY_READ) {
-                for sub in uninstall.enum_keys().flatten() {
-                    if let Ok(app) = uninstall.open_subkey_with_flags(sub, KEY_READ) {
-                        if let Ok(loc) = app.get_value::<String, _>("InstallLocation") {
-                            if !loc.trim().is_empty() {
-                                installed.push(PathBuf::from(loc));
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    let cutoff = age_threshold_days.unwrap_or(180);
-    let cutoff_duration = Duration::from_secs(cutoff as u64 * 24 * 60 * 60);
-    let now = SystemTime::now();
-
-    let mut candidates = Vec::new();
-    for root in roots {
-        if let Ok(entries) = std::fs::read_dir(&root) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if !path.is_dir() {
-                    continue;
-                }
-                if installed.iter().any(|p| path.starts_with(p)) {
-                    continue;
-                }
-                if let Ok(meta) = entry.metadata() {
-                    if let Ok(modified) = meta.modified() {
-                        if let Ok(age) = now.duration_since(modified) {
-                            if age >= cutoff_duration {
-                                candidates.push(path);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-    candidates
+    root.delete_subkey_all(path)
 }
 
 #[cfg(not(target_os = "windows"))]
-fn residue_candidates(_age_threshold_days: Option<i64>) -> Vec<PathBuf> {
-    Vec::new()
-}
-
-fn scan_directory(
-    base_path: &Path,
-    now: SystemTime,
-    age_threshold: Option<Duration>,
-    size_threshold: Option<u64>,
-    matcher: Option<&Pattern>,
-) -> (u64, u64) {
-    let mut total_bytes: u64 = 0;
-    let mut file_count: u64 = 0;
-    for entry in WalkDir::new(base_path)
-        .follow_links(false)
-        .into_iter()
-    {
-        let entry = match entry {
-            Ok(entry) => entry,
-            Err(_) => continue,
-        };
-        if !entry.file_type().is_file() {
-            continue;
-        }
-        if let Some(matcher) = matcher {
-            if let Some(rel) = entry.path().strip_prefix(base_path).ok() {
-                let rel_norm = normalize_path(rel);
-                if !matcher.matches(&rel_norm) {
-                    continue;
-                }
-            }
-        }
-        let meta = match entry.metadata() {
-            Ok(meta) => meta,
-            Err(_) => continue,
-        };
-        if !should_count(&meta, now, age_threshold, size_threshold) {
-            continue;
-        }
-        total_bytes += meta.len();
-        file_count += 1;
-    }
-    (total_bytes, file_count)
+fn delete_registry_key(_subkey: &str) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "Not supported"))
 }
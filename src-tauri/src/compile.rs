@@ -0,0 +1,113 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use glob::Pattern;
+use thiserror::Error;
+
+use crate::models::{Action, Rule, RuleType};
+use crate::paths::{self, PathError};
+
+/// Why a rule cannot be scanned or cleaned.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RuleError {
+    #[error("Action {action} is not valid for {rule_type} rules")]
+    ActionMismatch { rule_type: RuleType, action: Action },
+    #[error("Rule has no path")]
+    MissingPath,
+    #[error("Pattern rule has no pattern")]
+    MissingPattern,
+    #[error("Invalid pattern {pattern:?}: {message}")]
+    InvalidPattern { pattern: String, message: String },
+    #[error("Invalid {field}: {value}")]
+    InvalidThreshold { field: &'static str, value: i64 },
+    #[error("Rule has no tool command")]
+    MissingToolCommand,
+    #[error("Rule has an empty {0}")]
+    MissingField(&'static str),
+    #[error("Invalid tag {0:?}")]
+    InvalidTag(String),
+    #[error("Rule targets protected path {}", .0.display())]
+    ProtectedPath(PathBuf),
+    #[error(transparent)]
+    Path(#[from] PathError),
+}
+
+/// A rule whose pattern and thresholds have been validated and parsed.
+#[derive(Debug, Clone)]
+pub struct CompiledRule {
+    pub rule: Rule,
+    /// Resolved base of `path`/`pattern` rules; see `paths::resolve_path`.
+    pub base: Option<PathBuf>,
+    pub matcher: Option<Pattern>,
+    pub age_threshold: Option<Duration>,
+    pub size_threshold: Option<u64>,
+}
+
+/// Validates a rule up front so that nothing downstream has to guess.
+///
+/// An unparsable pattern is an error rather than "no filter": falling back to
+/// matching everything would clean the whole base directory.
+pub fn compile_rule(rule: &Rule) -> Result<CompiledRule, RuleError> {
+    let actions: &[Action] = match rule.rule_type {
+        RuleType::Path | RuleType::Pattern | RuleType::AppResidue => {
+            &[Action::Delete, Action::Recycle, Action::Quarantine]
+        }
+        RuleType::Special => &[Action::ToolCall],
+        RuleType::Registry => &[Action::Delete],
+    };
+    if !actions.contains(&rule.action) {
+        return Err(RuleError::ActionMismatch {
+            rule_type: rule.rule_type,
+            action: rule.action,
+        });
+    }
+    if rule.action == Action::ToolCall && rule.tool_cmd.as_deref().is_none_or(|cmd| cmd.trim().is_empty()) {
+        return Err(RuleError::MissingToolCommand);
+    }
+    let base = match rule.rule_type {
+        RuleType::Path | RuleType::Pattern => match rule.path.as_deref().map(str::trim) {
+            Some(path) if !path.is_empty() => Some(paths::resolve_path(path)?),
+            _ => return Err(RuleError::MissingPath),
+        },
+        _ => None,
+    };
+
+    let matcher = match rule.pattern.as_deref() {
+        Some(pattern) => Some(Pattern::new(&normalize_pattern(pattern)).map_err(|err| {
+            RuleError::InvalidPattern {
+                pattern: pattern.to_string(),
+                message: err.msg.to_string(),
+            }
+        })?),
+        None if rule.rule_type == RuleType::Pattern => return Err(RuleError::MissingPattern),
+        None => None,
+    };
+    let age_secs = threshold("age_threshold_days", rule.age_threshold_days, 24 * 60 * 60)?;
+    let size_bytes = threshold("size_threshold_mb", rule.size_threshold_mb, 1024 * 1024)?;
+
+    Ok(CompiledRule {
+        rule: rule.clone(),
+        base,
+        matcher,
+        age_threshold: age_secs.map(Duration::from_secs),
+        size_threshold: size_bytes,
+    })
+}
+
+/// Patterns are matched against `/`-separated relative paths.
+fn normalize_pattern(pattern: &str) -> String {
+    pattern.replace('\\', "/")
+}
+
+/// Converts a non-negative threshold to base units, rejecting negative or
+/// overflowing values.
+fn threshold(field: &'static str, value: Option<i64>, unit: u64) -> Result<Option<u64>, RuleError> {
+    match value {
+        None => Ok(None),
+        Some(value) => u64::try_from(value)
+            .ok()
+            .and_then(|value| value.checked_mul(unit))
+            .map(Some)
+            .ok_or(RuleError::InvalidThreshold { field, value }),
+    }
+}
@@ -2,9 +2,22 @@ use rusqlite::{Connection, OptionalExtension};
 use std::fs;
 use std::path::{Path, PathBuf};
 
-const SCHEMA_SQL: &str = include_str!("../../data/schema.sql");
-const SEED_SQL: &str = include_str!("../../data/seed.sql");
-const SEED_VERSION: &str = "2";
+use crate::profiles;
+use crate::seed;
+
+pub const SCHEMA_SQL: &str = include_str!("../../data/schema.sql");
+const SCHEMA_VERSION: i64 = 8;
+
+/// Upgrades applied in order to databases created by older builds.
+const MIGRATIONS: &[(i64, &str)] = &[
+    (2, include_str!("../../data/migrations/002_quarantine_action.sql")),
+    (3, include_str!("../../data/migrations/003_rule_origin.sql")),
+    (4, include_str!("../../data/migrations/004_rule_overrides.sql")),
+    (5, include_str!("../../data/migrations/005_profiles.sql")),
+    (6, include_str!("../../data/migrations/006_cleanup_history.sql")),
+    (7, include_str!("../../data/migrations/007_restored_bytes.sql")),
+    (8, include_str!("../../data/migrations/008_quarantine_status.sql")),
+];
 
 pub struct DbPaths {
     pub db_path: PathBuf,
@@ -15,7 +28,9 @@ pub fn init_db(data_dir: &Path) -> rusqlite::Result<DbPaths> {
     let db_path = data_dir.join("rules.db");
     let conn = Connection::open(&db_path)?;
     conn.execute_batch(SCHEMA_SQL)?;
+    migrate(&conn)?;
     ensure_default_settings(&conn)?;
+    profiles::ensure_default_profiles(&conn)?;
 
     let seed_version: Option<String> = conn
         .query_row(
@@ -25,9 +40,7 @@ pub fn init_db(data_dir: &Path) -> rusqlite::Result<DbPaths> {
         )
         .optional()?;
 
-    if seed_version.as_deref() != Some(SEED_VERSION) {
-        conn.execute_batch(SEED_SQL)?;
-    }
+    seed::apply_seed(&conn, seed_version.as_deref())?;
 
     Ok(DbPaths { db_path })
 }
@@ -36,10 +49,46 @@ pub fn open_db(db_path: &Path) -> rusqlite::Result<Connection> {
     Connection::open(db_path)
 }
 
+fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    let current: Option<String> = conn
+        .query_row(
+            "SELECT value FROM meta WHERE key = 'schema_version'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    // A database without a recorded version was just created from SCHEMA_SQL.
+    let current = current
+        .and_then(|value| value.parse::<i64>().ok())
+        .unwrap_or(SCHEMA_VERSION);
+    for (version, sql) in MIGRATIONS {
+        if *version > current {
+            conn.execute_batch(sql)?;
+        }
+    }
+    conn.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES ('schema_version', ?1)",
+        [SCHEMA_VERSION.to_string()],
+    )?;
+    Ok(())
+}
+
 fn ensure_default_settings(conn: &Connection) -> rusqlite::Result<()> {
     conn.execute(
         "INSERT OR IGNORE INTO settings (key, value) VALUES ('show_analysis','1')",
         [],
     )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO settings (key, value) VALUES ('quarantine_retention_days','30')",
+        [],
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO settings (key, value) VALUES ('protected_paths','')",
+        [],
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO settings (key, value) VALUES ('trusted_pack_keys','')",
+        [],
+    )?;
     Ok(())
 }
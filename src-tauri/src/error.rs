@@ -0,0 +1,108 @@
+use std::io;
+use std::sync::PoisonError;
+
+use rusqlite::ErrorCode;
+use serde::{Serialize, Serializer};
+use serde_json::json;
+use thiserror::Error;
+
+use crate::compile::RuleError;
+use crate::models::ErrorView;
+
+/// Errors returned by commands and jobs.
+///
+/// Serialized to the frontend as `{code, message, details}` so the UI can
+/// branch on `code` instead of parsing the message.
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error("Database error: {0}")]
+    Db(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Invalid rule {rule_id}: {source}")]
+    InvalidRule {
+        rule_id: String,
+        #[source]
+        source: RuleError,
+    },
+    #[error("Requires administrator privileges")]
+    RequiresAdmin,
+    #[error("Cancelled")]
+    Cancelled,
+    #[error("Unknown {kind}: {id}")]
+    NotFound { kind: &'static str, id: String },
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("Pack is not signed by a trusted key and contains tool or high-risk rules: {}", rule_ids.join(", "))]
+    UntrustedPack { rule_ids: Vec<String> },
+    #[error("{0}")]
+    Internal(String),
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn not_found(kind: &'static str, id: impl Into<String>) -> Self {
+        AppError::NotFound { kind, id: id.into() }
+    }
+
+    /// Stable identifier the frontend switches on.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Db(err) => match err.sqlite_error_code() {
+                Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) => "db_locked",
+                _ if matches!(err, rusqlite::Error::FromSqlConversionFailure(..)) => "invalid_data",
+                _ => "db",
+            },
+            AppError::Io(err) => match err.kind() {
+                io::ErrorKind::PermissionDenied => "permission_denied",
+                io::ErrorKind::NotFound => "not_found",
+                _ => "io",
+            },
+            AppError::InvalidRule { .. } => "invalid_rule",
+            AppError::RequiresAdmin => "requires_admin",
+            AppError::Cancelled => "cancelled",
+            AppError::NotFound { .. } => "not_found",
+            AppError::InvalidInput(_) => "invalid_input",
+            AppError::UntrustedPack { .. } => "untrusted_pack",
+            AppError::Internal(_) => "internal",
+        }
+    }
+
+    fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            AppError::Db(rusqlite::Error::SqliteFailure(err, _)) => Some(json!({ "sqlite_code": err.extended_code })),
+            AppError::Io(err) => Some(json!({ "kind": format!("{:?}", err.kind()) })),
+            AppError::InvalidRule { rule_id, .. } => Some(json!({ "rule_id": rule_id })),
+            AppError::NotFound { kind, id } => Some(json!({ "kind": kind, "id": id })),
+            AppError::UntrustedPack { rule_ids } => Some(json!({ "rule_ids": rule_ids })),
+            _ => None,
+        }
+    }
+
+    pub fn view(&self) -> ErrorView {
+        ErrorView {
+            code: self.code(),
+            message: self.to_string(),
+            details: self.details(),
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.view().serialize(serializer)
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        AppError::Internal(err.to_string())
+    }
+}
+
+impl<T> From<PoisonError<T>> for AppError {
+    fn from(_: PoisonError<T>) -> Self {
+        AppError::Internal("Application state lock poisoned".to_string())
+    }
+}
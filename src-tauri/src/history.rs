@@ -0,0 +1,174 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{Connection, OptionalExtension};
+
+use crate::error::{AppError, AppResult};
+use crate::ids;
+use crate::models::{CleanupReport, CleanupRunDetail, CleanupRunView, CleanupStats, SummaryBucket};
+
+const RUN_COLUMNS: &str = "id, created_at, profile, simulated, total_bytes, total_files, quarantine_run";
+
+/// Bytes of the cleanup item `i` of run `r` still held in quarantine.
+const HELD_BYTES: &str = "COALESCE((SELECT SUM(q.size) FROM quarantine_items q
+    WHERE q.run_id = r.quarantine_run AND q.rule_id = i.rule_id AND q.restored = 0), 0)";
+
+/// Stores a finished cleanup and returns its history id.
+pub fn record_run(conn: &Connection, profile: Option<&str>, report: &CleanupReport) -> AppResult<String> {
+    let id = ids::new_id("cleanup");
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "INSERT INTO cleanup_runs
+           (id, created_at, profile, simulated, total_bytes, total_files, quarantine_run, report)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        (
+            &id,
+            created_at as i64,
+            profile,
+            report.simulated,
+            report.summary.total_bytes as i64,
+            report.summary.total_files as i64,
+            &report.quarantine_run,
+            serde_json::to_string(report)?,
+        ),
+    )?;
+    for item in &report.items {
+        tx.execute(
+            "INSERT OR REPLACE INTO cleanup_run_items (run_id, rule_id, category, status, total_bytes, file_count)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            (
+                &id,
+                &item.id,
+                &item.category,
+                item.status.as_str(),
+                item.total_bytes as i64,
+                item.file_count as i64,
+            ),
+        )?;
+    }
+    tx.commit()?;
+    Ok(id)
+}
+
+/// Newest first; `limit` caps the number of runs returned.
+pub fn list_runs(conn: &Connection, limit: Option<usize>) -> rusqlite::Result<Vec<CleanupRunView>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM cleanup_runs ORDER BY created_at DESC, rowid DESC LIMIT ?1",
+        RUN_COLUMNS
+    ))?;
+    let limit = limit.map(|limit| limit as i64).unwrap_or(-1);
+    let rows = stmt.query_map([limit], run_from_row)?;
+    rows.collect()
+}
+
+pub fn get_run(conn: &Connection, id: &str) -> AppResult<CleanupRunDetail> {
+    let row = conn
+        .query_row(
+            &format!("SELECT {}, report FROM cleanup_runs WHERE id = ?1", RUN_COLUMNS),
+            [id],
+            |row| Ok((run_from_row(row)?, row.get::<_, String>(7)?)),
+        )
+        .optional()?;
+    let (run, report) = row.ok_or_else(|| AppError::not_found("cleanup run", id))?;
+    Ok(CleanupRunDetail {
+        run,
+        report: serde_json::from_str(&report)?,
+    })
+}
+
+/// Counts `bytes` of a quarantined file from cleanup run `quarantine_run`
+/// as restored, so they no longer count as reclaimed.
+pub fn record_restore(conn: &Connection, quarantine_run: &str, rule_id: &str, bytes: u64) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE cleanup_run_items SET restored_bytes = restored_bytes + ?3
+         WHERE rule_id = ?2 AND run_id IN (SELECT id FROM cleanup_runs WHERE quarantine_run = ?1)",
+        (quarantine_run, rule_id, bytes as i64),
+    )?;
+    Ok(())
+}
+
+/// Totals over every recorded cleanup that actually removed files.
+///
+/// Quarantined files still take up space and are reported on their own
+/// until purged; restored files are not counted at all.
+pub fn lifetime_stats(conn: &Connection) -> rusqlite::Result<CleanupStats> {
+    let (runs, total_files) = conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(total_files), 0) FROM cleanup_runs WHERE simulated = 0",
+        [],
+        |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64)),
+    )?;
+    let (total_bytes, quarantined_bytes) = conn.query_row(
+        &format!(
+            "SELECT COALESCE(SUM({}), 0), COALESCE(SUM({}), 0)
+             FROM cleanup_run_items i JOIN cleanup_runs r ON r.id = i.run_id
+             WHERE r.simulated = 0",
+            freed_bytes(),
+            HELD_BYTES
+        ),
+        [],
+        |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64)),
+    )?;
+    let by_category = buckets(conn, "i.category", "bytes DESC, key", total_bytes)?;
+    let by_rule = buckets(conn, "i.rule_id", "bytes DESC, key", total_bytes)?;
+    let by_month = buckets(
+        conn,
+        "strftime('%Y-%m', r.created_at, 'unixepoch')",
+        "key",
+        total_bytes,
+    )?;
+    Ok(CleanupStats {
+        runs,
+        total_bytes,
+        total_files,
+        quarantined_bytes,
+        by_category,
+        by_rule,
+        by_month,
+    })
+}
+
+/// Bytes the cleanup item `i` of run `r` actually freed.
+fn freed_bytes() -> String {
+    format!("MAX(i.total_bytes - i.restored_bytes - {}, 0)", HELD_BYTES)
+}
+
+/// Freed bytes of non-simulated runs grouped by `key`, leaving out groups
+/// that removed nothing.
+fn buckets(conn: &Connection, key: &str, order: &str, total: u64) -> rusqlite::Result<Vec<SummaryBucket>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} AS key, SUM({}) AS bytes, SUM(i.file_count)
+         FROM cleanup_run_items i JOIN cleanup_runs r ON r.id = i.run_id
+         WHERE r.simulated = 0
+         GROUP BY key
+         HAVING SUM(i.file_count) > 0
+         ORDER BY {}",
+        key,
+        freed_bytes(),
+        order
+    ))?;
+    let rows = stmt.query_map([], |row| {
+        let bytes = row.get::<_, i64>(1)? as u64;
+        Ok(SummaryBucket {
+            key: row.get(0)?,
+            bytes,
+            files: row.get::<_, i64>(2)? as u64,
+            percent: if total > 0 { bytes as f64 * 100.0 / total as f64 } else { 0.0 },
+        })
+    })?;
+    rows.collect()
+}
+
+fn run_from_row(row: &rusqlite::Row) -> rusqlite::Result<CleanupRunView> {
+    Ok(CleanupRunView {
+        id: row.get(0)?,
+        created_at: row.get::<_, i64>(1)? as u64,
+        profile: row.get(2)?,
+        simulated: row.get(3)?,
+        total_bytes: row.get::<_, i64>(4)? as u64,
+        total_files: row.get::<_, i64>(5)? as u64,
+        quarantine_run: row.get(6)?,
+    })
+}
@@ -0,0 +1,13 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Returns a process-unique id such as `plan-18c3f...-1`.
+pub fn new_id(prefix: &str) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("{}-{:x}-{}", prefix, nanos, NEXT_ID.fetch_add(1, Ordering::SeqCst))
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+use crate::ids;
+use crate::models::JobView;
+
+/// Finished jobs kept around for `job_status_cmd`/`list_jobs_cmd`.
+const MAX_FINISHED_JOBS: usize = 50;
+
+/// Cancellation flag owned by a single job.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Scan,
+    Preview,
+    Plan,
+    Cleanup,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+/// What a job's work closure gets to see.
+pub struct JobContext {
+    pub id: String,
+    pub cancel: CancelToken,
+}
+
+struct Job {
+    view: JobView,
+    cancel: CancelToken,
+}
+
+/// Runs scans and cleanups on background threads, one cancel token per job.
+#[derive(Default)]
+pub struct JobManager {
+    jobs: Mutex<HashMap<String, Job>>,
+}
+
+impl JobManager {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Starts `work` on its own thread and returns the job id immediately.
+    ///
+    /// `on_finish` is called with the final job state once `work` returns.
+    pub fn start<T, W, N>(self: &Arc<Self>, kind: JobKind, work: W, on_finish: N) -> String
+    where
+        T: Serialize,
+        W: FnOnce(&JobContext) -> AppResult<T> + Send + 'static,
+        N: FnOnce(&JobView) + Send + 'static,
+    {
+        let ctx = JobContext {
+            id: ids::new_id("job"),
+            cancel: CancelToken::new(),
+        };
+        let view = JobView {
+            id: ctx.id.clone(),
+            kind,
+            status: JobStatus::Running,
+            started_at: unix_now(),
+            finished_at: None,
+            result: None,
+            error: None,
+        };
+        self.lock().insert(
+            ctx.id.clone(),
+            Job {
+                view,
+                cancel: ctx.cancel.clone(),
+            },
+        );
+
+        let id = ctx.id.clone();
+        let manager = Arc::clone(self);
+        thread::spawn(move || {
+            let outcome = work(&ctx).and_then(|value| Ok(serde_json::to_value(value)?));
+            if let Some(view) = manager.finish(&ctx, outcome) {
+                on_finish(&view);
+            }
+        });
+        id
+    }
+
+    pub fn status(&self, id: &str) -> Option<JobView> {
+        self.lock().get(id).map(|job| job.view.clone())
+    }
+
+    /// Requests cancellation; returns false for unknown or finished jobs.
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.lock().get(id) {
+            Some(job) if job.view.status == JobStatus::Running => {
+                job.cancel.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn list(&self) -> Vec<JobView> {
+        let mut views: Vec<JobView> = self.lock().values().map(|job| job.view.clone()).collect();
+        views.sort_by(|a, b| b.started_at.cmp(&a.started_at).then_with(|| b.id.cmp(&a.id)));
+        views
+    }
+
+    fn finish(&self, ctx: &JobContext, outcome: AppResult<serde_json::Value>) -> Option<JobView> {
+        let mut jobs = self.lock();
+        let job = jobs.get_mut(&ctx.id)?;
+        job.view.finished_at = Some(unix_now());
+        match outcome {
+            Ok(value) => {
+                job.view.status = if ctx.cancel.is_cancelled() {
+                    JobStatus::Cancelled
+                } else {
+                    JobStatus::Completed
+                };
+                job.view.result = Some(value);
+            }
+            Err(err) => {
+                job.view.status = match err {
+                    AppError::Cancelled => JobStatus::Cancelled,
+                    _ => JobStatus::Failed,
+                };
+                job.view.error = Some(err.view());
+            }
+        }
+        let view = job.view.clone();
+        prune_finished(&mut jobs);
+        Some(view)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Job>> {
+        self.jobs.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn prune_finished(jobs: &mut HashMap<String, Job>) {
+    let mut finished: Vec<(u64, String)> = jobs
+        .values()
+        .filter_map(|job| job.view.finished_at.map(|at| (at, job.view.id.clone())))
+        .collect();
+    if finished.len() <= MAX_FINISHED_JOBS {
+        return;
+    }
+    finished.sort();
+    for (_, id) in finished.iter().take(finished.len() - MAX_FINISHED_JOBS) {
+        jobs.remove(id);
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
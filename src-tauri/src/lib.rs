@@ -0,0 +1,27 @@
+//! Scan and cleanup engine shared by the desktop app and `cdiskcleaner-cli`.
+
+pub mod cleanup;
+pub mod compile;
+pub mod db;
+pub mod error;
+pub mod history;
+pub mod ids;
+pub mod jobs;
+pub mod models;
+pub mod overlap;
+pub mod packs;
+pub mod paths;
+pub mod plan;
+pub mod preview;
+pub mod privilege;
+pub mod profiles;
+pub mod protect;
+pub mod quarantine;
+pub mod rules;
+pub mod scan;
+pub mod seed;
+pub mod settings;
+pub mod signing;
+pub mod sizes;
+pub mod sources;
+pub mod tags;
@@ -1,81 +1,540 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod db;
-mod cleanup;
-mod models;
-mod privilege;
-mod rules;
-mod scan;
-mod settings;
-
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use tauri::{Emitter, Manager, State};
 
+use cdiskcleaner::error::{AppError, AppResult};
+use cdiskcleaner::{
+    cleanup, db, history, jobs, models, packs, plan, preview, privilege, profiles, protect, quarantine, rules, scan, seed,
+    settings, signing, sources,
+};
+
 struct AppState {
     db_path: PathBuf,
+    quarantine: quarantine::QuarantineStore,
+    plans: Mutex<HashMap<String, models::CleanupPlan>>,
+    /// Latest scan result per rule, which plans are checked against.
+    scans: Mutex<HashMap<String, models::RuleScan>>,
+    previews: Mutex<preview::PreviewSessions>,
+    jobs: Arc<jobs::JobManager>,
+    sources: sources::SourceDirs,
 }
 
 #[tauri::command]
-fn list_rules_cmd(state: State<'_, AppState>) -> Result<Vec<models::RuleView>, String> {
-    let conn = db::open_db(&state.db_path).map_err(|e| e.to_string())?;
+fn list_rules_cmd(state: State<'_, AppState>) -> AppResult<Vec<models::RuleView>> {
+    let conn = db::open_db(&state.db_path)?;
     let is_admin = privilege::is_admin();
-    rules::list_rules_with_privilege(&conn, is_admin).map_err(|e| e.to_string())
+    rules::list_rules_with_privilege(&conn, &state.sources, is_admin)
+}
+
+/// Where rules are read from, and the pack or override files that were skipped.
+#[tauri::command]
+fn rule_sources_cmd(state: State<'_, AppState>) -> AppResult<models::RuleSourcesView> {
+    let conn = db::open_db(&state.db_path)?;
+    let loaded = sources::load_rules(&conn, &state.sources)?;
+    Ok(state.sources.view(loaded.problems))
+}
+
+#[tauri::command]
+fn create_rule_cmd(state: State<'_, AppState>, rule: models::RuleInput) -> AppResult<models::Rule> {
+    let conn = db::open_db(&state.db_path)?;
+    rules::create_rule(&conn, rule)
+}
+
+#[tauri::command]
+fn update_rule_cmd(state: State<'_, AppState>, rule_id: String, rule: models::RuleInput) -> AppResult<models::Rule> {
+    let conn = db::open_db(&state.db_path)?;
+    rules::update_rule(&conn, &rule_id, rule)
+}
+
+#[tauri::command]
+fn duplicate_rule_cmd(state: State<'_, AppState>, rule_id: String) -> AppResult<models::Rule> {
+    let conn = db::open_db(&state.db_path)?;
+    rules::duplicate_rule(&conn, &rule_id)
+}
+
+#[tauri::command]
+fn set_rule_enabled_cmd(state: State<'_, AppState>, rule_id: String, enabled: bool) -> AppResult<()> {
+    let conn = db::open_db(&state.db_path)?;
+    match rules::set_rule_enabled(&conn, &rule_id, enabled) {
+        Err(AppError::NotFound { .. }) => {
+            let fields = serde_json::json!({ "enabled": enabled });
+            sources::override_pack_rule(&conn, &state.sources, &rule_id, fields).map(|_| ())
+        }
+        result => result,
+    }
+}
+
+#[tauri::command]
+fn add_rule_tag_cmd(state: State<'_, AppState>, rule_id: String, tag: String) -> AppResult<Vec<String>> {
+    let conn = db::open_db(&state.db_path)?;
+    sources::edit_tag(&conn, &state.sources, &rule_id, &tag, true)
+}
+
+#[tauri::command]
+fn remove_rule_tag_cmd(state: State<'_, AppState>, rule_id: String, tag: String) -> AppResult<Vec<String>> {
+    let conn = db::open_db(&state.db_path)?;
+    sources::edit_tag(&conn, &state.sources, &rule_id, &tag, false)
+}
+
+#[tauri::command]
+fn delete_rule_cmd(state: State<'_, AppState>, rule_id: String) -> AppResult<()> {
+    let conn = db::open_db(&state.db_path)?;
+    rules::delete_rule(&conn, &rule_id)
+}
+
+#[tauri::command]
+fn tune_rule_cmd(state: State<'_, AppState>, rule_id: String, tuning: models::RuleTuning) -> AppResult<models::Rule> {
+    let conn = db::open_db(&state.db_path)?;
+    match rules::tune_rule(&conn, &rule_id, tuning.clone()) {
+        Err(AppError::NotFound { .. }) => {
+            sources::override_pack_rule(&conn, &state.sources, &rule_id, serde_json::to_value(tuning)?)
+        }
+        result => result,
+    }
+}
+
+#[tauri::command]
+fn reset_rule_cmd(state: State<'_, AppState>, rule_id: String) -> AppResult<models::Rule> {
+    let conn = db::open_db(&state.db_path)?;
+    rules::reset_rule(&conn, &rule_id)
+}
+
+/// What the last seed upgrade added, changed or removed, until dismissed.
+#[tauri::command]
+fn last_seed_upgrade_cmd(state: State<'_, AppState>) -> AppResult<Option<models::SeedUpgrade>> {
+    let conn = db::open_db(&state.db_path)?;
+    seed::last_seed_upgrade(&conn).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn dismiss_seed_upgrade_cmd(state: State<'_, AppState>) -> AppResult<()> {
+    let conn = db::open_db(&state.db_path)?;
+    seed::dismiss_seed_upgrade(&conn).map_err(AppError::from)
+}
+
+/// Serializes `rule_ids` (default: every user rule) as a TOML or JSON pack.
+#[tauri::command]
+fn export_rules_cmd(
+    state: State<'_, AppState>,
+    name: String,
+    version: String,
+    author: Option<String>,
+    rule_ids: Option<Vec<String>>,
+    format: models::PackFormat,
+) -> AppResult<String> {
+    let conn = db::open_db(&state.db_path)?;
+    let pack = packs::export_pack(&conn, &name, &version, author, rule_ids.as_deref())?;
+    packs::write_pack(&pack, format)
+}
+
+/// Signs exported pack text with a maintainer's base64 ed25519 secret key.
+#[tauri::command]
+fn sign_pack_cmd(contents: String, signing_key: String) -> AppResult<models::PackSignature> {
+    Ok(models::PackSignature {
+        signature: signing::sign(&contents, &signing_key)?,
+        public_key: signing::public_key(&signing_key)?,
+    })
+}
+
+/// Imports a pack; `signature` is its detached base64 ed25519 signature.
+#[tauri::command]
+fn import_rules_cmd(
+    state: State<'_, AppState>,
+    contents: String,
+    signature: Option<String>,
+    format: Option<models::PackFormat>,
+    options: Option<models::ImportOptions>,
+) -> AppResult<models::ImportReport> {
+    let conn = db::open_db(&state.db_path)?;
+    packs::import_text(
+        &conn,
+        &state.sources,
+        &contents,
+        signature.as_deref(),
+        format,
+        &options.unwrap_or_default(),
+    )
+}
+
+#[tauri::command]
+fn list_profiles_cmd(state: State<'_, AppState>) -> AppResult<Vec<models::Profile>> {
+    let conn = db::open_db(&state.db_path)?;
+    profiles::list_profiles(&conn).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn save_profile_cmd(state: State<'_, AppState>, profile: models::Profile) -> AppResult<models::Profile> {
+    let conn = db::open_db(&state.db_path)?;
+    profiles::save_profile(&conn, profile)
+}
+
+#[tauri::command]
+fn delete_profile_cmd(state: State<'_, AppState>, name: String) -> AppResult<()> {
+    let conn = db::open_db(&state.db_path)?;
+    profiles::delete_profile(&conn, &name)
+}
+
+/// Serializes `names` (default: every profile) as TOML or JSON.
+#[tauri::command]
+fn export_profiles_cmd(
+    state: State<'_, AppState>,
+    names: Option<Vec<String>>,
+    format: models::PackFormat,
+) -> AppResult<String> {
+    let conn = db::open_db(&state.db_path)?;
+    profiles::export_profiles(&conn, names.as_deref(), format)
+}
+
+#[tauri::command]
+fn import_profiles_cmd(
+    state: State<'_, AppState>,
+    contents: String,
+    format: Option<models::PackFormat>,
+    replace: Option<bool>,
+) -> AppResult<models::ProfileImport> {
+    let conn = db::open_db(&state.db_path)?;
+    profiles::import_profiles(&conn, &contents, format, replace.unwrap_or(false))
 }
 
 #[tauri::command]
-fn privilege_state_cmd() -> Result<bool, String> {
+fn privilege_state_cmd() -> AppResult<bool> {
     Ok(privilege::is_admin())
 }
 
+/// Forwards a finished job to the frontend as `job:finished`.
+fn notify_finished(app: &tauri::AppHandle) -> impl FnOnce(&models::JobView) + Send + 'static {
+    let app = app.clone();
+    move |view: &models::JobView| {
+        let _ = app.emit("job:finished", view.clone());
+    }
+}
+
+/// Scans every enabled rule, or those of `profile` or matching the tag
+/// expression `tags`.
+#[tauri::command]
+fn start_scan_job_cmd(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    profile: Option<String>,
+    tags: Option<String>,
+) -> AppResult<String> {
+    let conn = db::open_db(&state.db_path)?;
+    let rules = if profile.is_some() || tags.is_some() {
+        let selection = profiles::select_rules(&conn, &state.sources, profile.as_deref(), None, tags.as_deref())?;
+        let mut rules = selection.rules;
+        rules.retain(|rule| selection.selected.contains(&rule.id));
+        rules
+    } else {
+        sources::enabled_rules(&conn, &state.sources)?
+    };
+    let is_admin = privilege::is_admin();
+    let emitter = app.clone();
+    let handle = app.clone();
+    let work = move |job: &jobs::JobContext| {
+        let mut progress = |rule: &models::Rule| {
+            let _ = emitter.emit(
+                "scan:progress",
+                models::JobEvent {
+                    job_id: job.id.clone(),
+                    payload: serde_json::json!({
+                        "id": rule.id,
+                        "title": rule.title,
+                        "path": rule.path
+                    }),
+                },
+            );
+        };
+        let options = scan::ScanOptions {
+            is_admin,
+            cancel: job.cancel.clone(),
+        };
+        let results = scan::scan_rules(&rules, &options, &mut progress);
+        let state = handle.state::<AppState>();
+        let mut scans = state.scans.lock()?;
+        for result in &results {
+            scans.insert(result.id.clone(), result.clone());
+        }
+        Ok(results)
+    };
+    Ok(state.jobs.start(jobs::JobKind::Scan, work, notify_finished(&app)))
+}
+
+/// Walks one rule in the background for the file preview; page through the
+/// result with `preview_page_cmd` and drop it with `discard_preview_cmd`.
+/// Only the latest few sessions are kept; see `preview::PreviewSessions`.
 #[tauri::command]
-fn scan_rules_cmd(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<Vec<models::RuleScan>, String> {
-    let conn = db::open_db(&state.db_path).map_err(|e| e.to_string())?;
-    let rules = rules::list_rules(&conn).map_err(|e| e.to_string())?;
+fn start_preview_job_cmd(app: tauri::AppHandle, state: State<'_, AppState>, rule_id: String) -> AppResult<String> {
+    let conn = db::open_db(&state.db_path)?;
+    let rule = sources::enabled_rules(&conn, &state.sources)?
+        .into_iter()
+        .find(|rule| rule.id == rule_id)
+        .ok_or_else(|| AppError::not_found("rule", &rule_id))?;
     let is_admin = privilege::is_admin();
-    scan::clear_cancel();
-    let mut progress = |rule: &models::Rule| {
-        let _ = app.emit(
-            "scan:progress",
-            serde_json::json!({
-                "id": rule.id,
-                "title": rule.title,
-                "path": rule.path
-            }),
-        );
+    preview::check_rule(&rule, is_admin)?;
+    let handle = app.clone();
+    let work = move |job: &jobs::JobContext| {
+        let options = scan::ScanOptions {
+            is_admin,
+            cancel: job.cancel.clone(),
+        };
+        let mut session = preview::walk_preview(&rule, &options);
+        if job.cancel.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+        let first = preview::page(&mut session, preview::PreviewSort::default(), 0, None);
+        let state = handle.state::<AppState>();
+        state.previews.lock()?.insert(session);
+        Ok(first)
     };
-    Ok(scan::scan_rules(&rules, &scan::ScanOptions { is_admin }, &mut progress))
+    Ok(state.jobs.start(jobs::JobKind::Preview, work, notify_finished(&app)))
+}
+
+#[tauri::command]
+fn preview_page_cmd(
+    state: State<'_, AppState>,
+    session_id: String,
+    sort: Option<preview::PreviewSort>,
+    page: Option<usize>,
+    page_size: Option<usize>,
+) -> AppResult<models::PreviewPage> {
+    let mut previews = state.previews.lock()?;
+    let session = previews
+        .get_mut(&session_id)
+        .ok_or_else(|| AppError::not_found("preview", &session_id))?;
+    Ok(preview::page(session, sort.unwrap_or_default(), page.unwrap_or(0), page_size))
+}
+
+#[tauri::command]
+fn discard_preview_cmd(state: State<'_, AppState>, session_id: String) -> AppResult<()> {
+    state.previews.lock()?.remove(&session_id);
+    Ok(())
 }
 
+/// Plans the rules of `profile`, or `selected_ids`, the rules matching
+/// `tags`, or both intersected. Rules whose matches changed since the last
+/// scan are listed in the summary's `drift`.
 #[tauri::command]
-fn clean_rules_cmd(state: State<'_, AppState>, selected_ids: Vec<String>) -> Result<models::CleanupReport, String> {
-    let conn = db::open_db(&state.db_path).map_err(|e| e.to_string())?;
-    let rules = rules::list_rules(&conn).map_err(|e| e.to_string())?;
+fn start_plan_job_cmd(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    profile: Option<String>,
+    selected_ids: Option<Vec<String>>,
+    tags: Option<String>,
+) -> AppResult<String> {
+    let conn = db::open_db(&state.db_path)?;
+    let selection =
+        profiles::select_rules(&conn, &state.sources, profile.as_deref(), selected_ids, tags.as_deref())?;
     let is_admin = privilege::is_admin();
-    Ok(cleanup::cleanup_rules(
-        &rules,
-        &selected_ids,
-        &cleanup::CleanupOptions { is_admin },
-    ))
+    let handle = app.clone();
+    let work = move |job: &jobs::JobContext| {
+        let options = scan::ScanOptions {
+            is_admin,
+            cancel: job.cancel.clone(),
+        };
+        let mut plan = plan::build_plan(&selection.rules, &selection.selected, &options);
+        plan.profile = profile.clone();
+        plan.unavailable = selection.unavailable.clone();
+        // A partially walked plan must never be executed.
+        if job.cancel.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+        let state = handle.state::<AppState>();
+        plan::compare_with_scan(&mut plan, &*state.scans.lock()?);
+        let summary = plan::summarize_plan(&plan);
+        state.plans.lock()?.insert(plan.id.clone(), plan);
+        Ok(summary)
+    };
+    Ok(state.jobs.start(jobs::JobKind::Plan, work, notify_finished(&app)))
+}
+
+/// Runs a plan built by `start_plan_job_cmd` and reviewed by the user.
+///
+/// A plan whose files changed since the scan the user saw is refused unless
+/// `accept_drift` is set after showing `drift`. A `dry_run` leaves the plan
+/// in place so it can be run for real afterwards.
+#[tauri::command]
+fn start_cleanup_job_cmd(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    plan_id: String,
+    accept_drift: Option<bool>,
+    dry_run: Option<bool>,
+) -> AppResult<String> {
+    let dry_run = dry_run.unwrap_or(false);
+    let plan = {
+        let mut plans = state.plans.lock()?;
+        let Entry::Occupied(plan) = plans.entry(plan_id.clone()) else {
+            return Err(AppError::not_found("plan", &plan_id));
+        };
+        let drift = &plan.get().drift;
+        if !drift.is_empty() && !accept_drift.unwrap_or(false) {
+            let rule_ids: Vec<&str> = drift.iter().map(|drift| drift.rule_id.as_str()).collect();
+            return Err(AppError::InvalidInput(format!(
+                "Files matched by {} changed since the scan; review the plan again",
+                rule_ids.join(", ")
+            )));
+        }
+        if dry_run {
+            plan.get().clone()
+        } else {
+            plan.remove()
+        }
+    };
+    let run_profile = plan.profile.clone();
+    let db_path = state.db_path.clone();
+    let store = state.quarantine.clone();
+    let protected = {
+        let conn = db::open_db(&state.db_path)?;
+        protect::ProtectedPaths::load(&conn)?
+    };
+    let is_admin = privilege::is_admin();
+    let emitter = app.clone();
+    let work = move |job: &jobs::JobContext| {
+        let options = cleanup::CleanupOptions {
+            is_admin,
+            quarantine: Some(store.clone()),
+            protected: protected.clone(),
+            cancel: job.cancel.clone(),
+            dry_run,
+        };
+        let mut progress = |update: &models::CleanupProgress| {
+            let _ = emitter.emit(
+                "cleanup:progress",
+                models::JobEvent {
+                    job_id: job.id.clone(),
+                    payload: update.clone(),
+                },
+            );
+        };
+        let mut report = cleanup::execute_plan(&plan, &options, &mut progress);
+        let recorded = db::open_db(&db_path).map_err(AppError::from).and_then(|conn| {
+            history::record_run(&conn, run_profile.as_deref(), &report)?;
+            if !dry_run {
+                let _ = quarantine::purge_expired(&conn, &store);
+            }
+            Ok(())
+        });
+        if let Err(err) = recorded {
+            report.errors.push(format!("Could not record cleanup history: {}", err));
+        }
+        Ok(report)
+    };
+    Ok(state.jobs.start(jobs::JobKind::Cleanup, work, notify_finished(&app)))
+}
+
+#[tauri::command]
+fn job_status_cmd(state: State<'_, AppState>, job_id: String) -> AppResult<models::JobView> {
+    state
+        .jobs
+        .status(&job_id)
+        .ok_or_else(|| AppError::not_found("job", &job_id))
+}
+
+#[tauri::command]
+fn cancel_job_cmd(state: State<'_, AppState>, job_id: String) -> AppResult<bool> {
+    Ok(state.jobs.cancel(&job_id))
 }
 
 #[tauri::command]
-fn get_setting_cmd(state: State<'_, AppState>, key: String) -> Result<Option<String>, String> {
-    let conn = db::open_db(&state.db_path).map_err(|e| e.to_string())?;
-    settings::get_setting(&conn, &key).map_err(|e| e.to_string())
+fn list_jobs_cmd(state: State<'_, AppState>) -> AppResult<Vec<models::JobView>> {
+    Ok(state.jobs.list())
 }
 
 #[tauri::command]
-fn set_setting_cmd(state: State<'_, AppState>, key: String, value: String) -> Result<(), String> {
-    let conn = db::open_db(&state.db_path).map_err(|e| e.to_string())?;
-    settings::set_setting(&conn, &key, &value).map_err(|e| e.to_string())
+fn exclude_plan_paths_cmd(
+    state: State<'_, AppState>,
+    plan_id: String,
+    paths: Vec<String>,
+) -> AppResult<models::PlanSummary> {
+    let mut plans = state.plans.lock()?;
+    let plan = plans
+        .get_mut(&plan_id)
+        .ok_or_else(|| AppError::not_found("plan", &plan_id))?;
+    plan::exclude_paths(plan, &paths);
+    Ok(plan::summarize_plan(plan))
 }
 
 #[tauri::command]
-fn cancel_scan_cmd() -> Result<(), String> {
-    scan::request_cancel();
+fn discard_plan_cmd(state: State<'_, AppState>, plan_id: String) -> AppResult<()> {
+    state.plans.lock()?.remove(&plan_id);
     Ok(())
 }
 
+#[tauri::command]
+fn list_cleanup_runs_cmd(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+) -> AppResult<Vec<models::CleanupRunView>> {
+    let conn = db::open_db(&state.db_path)?;
+    history::list_runs(&conn, limit).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn get_cleanup_run_cmd(state: State<'_, AppState>, run_id: String) -> AppResult<models::CleanupRunDetail> {
+    let conn = db::open_db(&state.db_path)?;
+    history::get_run(&conn, &run_id)
+}
+
+#[tauri::command]
+fn cleanup_stats_cmd(state: State<'_, AppState>) -> AppResult<models::CleanupStats> {
+    let conn = db::open_db(&state.db_path)?;
+    history::lifetime_stats(&conn).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn list_quarantine_runs_cmd(state: State<'_, AppState>) -> AppResult<Vec<models::QuarantineRunView>> {
+    let conn = db::open_db(&state.db_path)?;
+    quarantine::list_runs(&conn).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn list_quarantine_items_cmd(
+    state: State<'_, AppState>,
+    run_id: String,
+) -> AppResult<Vec<models::QuarantineItemView>> {
+    let conn = db::open_db(&state.db_path)?;
+    quarantine::list_items(&conn, &run_id).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn restore_quarantine_run_cmd(state: State<'_, AppState>, run_id: String) -> AppResult<models::RestoreReport> {
+    let conn = db::open_db(&state.db_path)?;
+    quarantine::restore_run(&conn, &run_id).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn purge_quarantine_run_cmd(state: State<'_, AppState>, run_id: String) -> AppResult<()> {
+    let conn = db::open_db(&state.db_path)?;
+    quarantine::purge_run(&conn, &state.quarantine, &run_id).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn get_setting_cmd(state: State<'_, AppState>, key: String) -> AppResult<Option<String>> {
+    let conn = db::open_db(&state.db_path)?;
+    settings::get_setting(&conn, &key).map_err(AppError::from)
+}
+
+/// Entries of the `protected_paths` setting that do not resolve and so
+/// protect nothing, for the settings view to warn about.
+#[tauri::command]
+fn protected_path_warnings_cmd(state: State<'_, AppState>) -> AppResult<Vec<models::InvalidProtectedPath>> {
+    let conn = db::open_db(&state.db_path)?;
+    Ok(protect::ProtectedPaths::load(&conn)?.invalid().to_vec())
+}
+
+#[tauri::command]
+fn set_setting_cmd(state: State<'_, AppState>, key: String, value: String) -> AppResult<()> {
+    if key == protect::PROTECTED_SETTING {
+        protect::validate_setting(&value)?;
+    }
+    let conn = db::open_db(&state.db_path)?;
+    settings::set_setting(&conn, &key, &value).map_err(AppError::from)
+}
+
 fn main() {
     tauri::Builder::default()
         .setup(|app| {
@@ -84,18 +543,69 @@ fn main() {
                 .app_data_dir()
                 .map_err(|e| e.to_string())?;
             let db_paths = db::init_db(&data_dir).map_err(|e| e.to_string())?;
+            let rule_sources = sources::SourceDirs::new(&data_dir);
+            rule_sources.create_user_dirs();
+            let quarantine = quarantine::QuarantineStore {
+                root: data_dir.join("quarantine"),
+                db_path: db_paths.db_path.clone(),
+            };
+            if let Ok(conn) = db::open_db(&db_paths.db_path) {
+                let _ = quarantine::purge_expired(&conn, &quarantine);
+            }
             app.manage(AppState {
                 db_path: db_paths.db_path,
+                quarantine,
+                plans: Mutex::new(HashMap::new()),
+                scans: Mutex::new(HashMap::new()),
+                previews: Mutex::new(preview::PreviewSessions::default()),
+                jobs: jobs::JobManager::new(),
+                sources: rule_sources,
             });
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             list_rules_cmd,
+            rule_sources_cmd,
+            create_rule_cmd,
+            update_rule_cmd,
+            duplicate_rule_cmd,
+            set_rule_enabled_cmd,
+            add_rule_tag_cmd,
+            remove_rule_tag_cmd,
+            delete_rule_cmd,
+            tune_rule_cmd,
+            reset_rule_cmd,
+            last_seed_upgrade_cmd,
+            dismiss_seed_upgrade_cmd,
+            export_rules_cmd,
+            import_rules_cmd,
+            sign_pack_cmd,
+            list_profiles_cmd,
+            save_profile_cmd,
+            delete_profile_cmd,
+            export_profiles_cmd,
+            import_profiles_cmd,
             privilege_state_cmd,
-            scan_rules_cmd,
-            cancel_scan_cmd,
-            clean_rules_cmd,
+            start_scan_job_cmd,
+            start_preview_job_cmd,
+            preview_page_cmd,
+            discard_preview_cmd,
+            start_plan_job_cmd,
+            exclude_plan_paths_cmd,
+            discard_plan_cmd,
+            start_cleanup_job_cmd,
+            job_status_cmd,
+            cancel_job_cmd,
+            list_jobs_cmd,
+            list_cleanup_runs_cmd,
+            get_cleanup_run_cmd,
+            cleanup_stats_cmd,
+            list_quarantine_runs_cmd,
+            list_quarantine_items_cmd,
+            restore_quarantine_run_cmd,
+            purge_quarantine_run_cmd,
             get_setting_cmd,
+            protected_path_warnings_cmd,
             set_setting_cmd
         ])
         .run(tauri::generate_context!())
@@ -1,25 +1,159 @@
-use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Serialize)]
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+
+use crate::jobs::{JobKind, JobStatus};
+use crate::sizes::FileId;
+
+/// Declares a fieldless enum stored as snake_case text in the database and
+/// over IPC, so unknown values fail to parse instead of being string-compared.
+macro_rules! text_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident => $text:literal,)+ }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[serde(rename_all = "snake_case")]
+        pub enum $name {
+            $($variant,)+
+        }
+
+        impl $name {
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $($name::$variant => $text,)+
+                }
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = String;
+
+            fn from_str(text: &str) -> Result<Self, String> {
+                match text {
+                    $($text => Ok($name::$variant),)+
+                    other => Err(format!("Unknown {}: {:?}", stringify!($name), other)),
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl FromSql for $name {
+            fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+                value
+                    .as_str()?
+                    .parse()
+                    .map_err(|err: String| FromSqlError::Other(err.into()))
+            }
+        }
+
+        impl ToSql for $name {
+            fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+                Ok(ToSqlOutput::from(self.as_str()))
+            }
+        }
+    };
+}
+
+text_enum!(RuleType {
+    Path => "path",
+    Pattern => "pattern",
+    Special => "special",
+    Registry => "registry",
+    AppResidue => "app_residue",
+});
+
+text_enum!(Risk {
+    Low => "low",
+    Medium => "medium",
+    High => "high",
+});
+
+text_enum!(Scope {
+    System => "system",
+    User => "user",
+    Both => "both",
+});
+
+text_enum!(Action {
+    Delete => "delete",
+    Recycle => "recycle",
+    Quarantine => "quarantine",
+    ToolCall => "tool_call",
+});
+
+text_enum!(
+    /// Where a rule came from. Seed upgrades only ever replace `Seed` rules;
+    /// `Pack` rules are read from a pack directory and never stored.
+    RuleOrigin {
+        Seed => "seed",
+        User => "user",
+        Pack => "pack",
+    }
+);
+
+text_enum!(
+    /// Layer a rule field was taken from, lowest precedence first.
+    RuleSource {
+        Builtin => "builtin",
+        System => "system",
+        User => "user",
+        Override => "override",
+    }
+);
+
+text_enum!(PackFormat {
+    Toml => "toml",
+    Json => "json",
+});
+
+text_enum!(
+    /// Outcome of scanning, planning, previewing or cleaning one rule.
+    RuleStatus {
+        Ok => "ok",
+        Partial => "partial",
+        Pending => "pending",
+        Cancelled => "cancelled",
+        Blocked => "blocked",
+        Invalid => "invalid",
+        Protected => "protected",
+        Unsupported => "unsupported",
+        MissingPath => "missing_path",
+        Missing => "missing",
+        Skipped => "skipped",
+        Error => "error",
+    }
+);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Rule {
     pub id: String,
     pub title: String,
     pub description: String,
     pub category: String,
-    pub risk: String,
+    pub risk: Risk,
     pub default_checked: bool,
     pub requires_admin: bool,
-    pub rule_type: String,
-    pub scope: String,
+    pub rule_type: RuleType,
+    pub scope: Scope,
     pub path: Option<String>,
     pub pattern: Option<String>,
     pub size_threshold_mb: Option<i64>,
     pub age_threshold_days: Option<i64>,
-    pub action: String,
+    pub action: Action,
     pub tool_cmd: Option<String>,
     pub enabled: bool,
     pub sort_order: i64,
     pub notes: Option<String>,
+    pub origin: RuleOrigin,
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -28,48 +162,396 @@ pub struct RuleView {
     pub title: String,
     pub description: String,
     pub category: String,
-    pub risk: String,
+    pub risk: Risk,
     pub default_checked: bool,
     pub requires_admin: bool,
-    pub rule_type: String,
-    pub scope: String,
+    pub rule_type: RuleType,
+    pub scope: Scope,
     pub path: Option<String>,
     pub pattern: Option<String>,
     pub size_threshold_mb: Option<i64>,
     pub age_threshold_days: Option<i64>,
-    pub action: String,
+    pub action: Action,
     pub tool_cmd: Option<String>,
     pub enabled: bool,
     pub sort_order: i64,
     pub notes: Option<String>,
+    pub origin: RuleOrigin,
+    pub tags: Vec<String>,
+    /// Layer that defined the rule.
+    pub source: RuleSource,
+    /// Layer each effective field was taken from.
+    pub field_sources: BTreeMap<String, RuleSource>,
+    /// Fields set by the override layer; seed upgrades keep them.
+    pub overridden: Vec<String>,
     pub blocked: bool,
     pub blocked_reason: Option<String>,
+    /// Set when the rule fails validation; such rules are never scanned or cleaned.
+    pub invalid_reason: Option<String>,
+}
+
+/// The editable fields of a user-defined rule.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuleInput {
+    pub title: String,
+    pub description: String,
+    pub category: String,
+    pub risk: Risk,
+    #[serde(default)]
+    pub default_checked: bool,
+    #[serde(default)]
+    pub requires_admin: bool,
+    pub rule_type: RuleType,
+    pub scope: Scope,
+    pub path: Option<String>,
+    pub pattern: Option<String>,
+    pub size_threshold_mb: Option<i64>,
+    pub age_threshold_days: Option<i64>,
+    pub action: Action,
+    pub tool_cmd: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Fields a user may change on a built-in rule without duplicating it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleTuning {
+    pub default_checked: bool,
+    pub action: Action,
+    pub age_threshold_days: Option<i64>,
+    pub size_threshold_mb: Option<i64>,
+}
+
+/// What a seed upgrade did to one existing rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleChange {
+    pub id: String,
+    /// Fields that took the new seed value.
+    pub fields: Vec<String>,
+    /// Fields the seed changed but the user had overridden, left as they were.
+    pub kept_overrides: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedUpgrade {
+    pub from_version: Option<String>,
+    pub to_version: String,
+    pub added: Vec<String>,
+    pub changed: Vec<RuleChange>,
+    pub removed: Vec<String>,
+    /// Seed rules not installed because an imported user rule has their id.
+    #[serde(default)]
+    pub shadowed: Vec<String>,
+}
+
+/// A shareable set of rules, stored as TOML or JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RulePack {
+    pub name: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<PackRule>,
+}
+
+/// A rule as it appears in a pack: the rule's own fields plus its tags.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackRule {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub category: String,
+    pub risk: Risk,
+    #[serde(default)]
+    pub default_checked: bool,
+    #[serde(default)]
+    pub requires_admin: bool,
+    pub rule_type: RuleType,
+    pub scope: Scope,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_threshold_mb: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub age_threshold_days: Option<i64>,
+    pub action: Action,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_cmd: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// An override file: field values per rule id, applied on top of every
+/// other source. Only tunable fields may be set; see `sources::load_rules`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleOverrides {
+    #[serde(default)]
+    pub rules: BTreeMap<String, serde_json::Map<String, serde_json::Value>>,
+}
+
+/// A rule after merging every source, with where each field came from.
+#[derive(Debug, Clone)]
+pub struct EffectiveRule {
+    pub rule: Rule,
+    pub source: RuleSource,
+    pub field_sources: BTreeMap<String, RuleSource>,
+}
+
+/// A pack or override file, or an entry in one, that was skipped.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceProblem {
+    pub path: String,
+    pub message: String,
+}
+
+/// A `protected_paths` entry that does not resolve and so protects nothing.
+#[derive(Debug, Clone, Serialize)]
+pub struct InvalidProtectedPath {
+    pub entry: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RuleSourcesView {
+    pub system_dir: Option<String>,
+    pub user_dir: Option<String>,
+    pub overrides_dir: Option<String>,
+    pub problems: Vec<SourceProblem>,
+}
+
+/// Rules a profile lists by id that it could not run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UnavailableRules {
+    /// No rule has this id, e.g. a pack rule whose pack was removed.
+    pub unknown: Vec<String>,
+    pub disabled: Vec<String>,
+}
+
+impl UnavailableRules {
+    pub fn is_empty(&self) -> bool {
+        self.unknown.is_empty() && self.disabled.is_empty()
+    }
+}
+
+/// A saved rule selection: the rules listed in `rules` plus every rule
+/// matching the tag expression `tags`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<ProfileRule>,
+}
+
+/// A rule picked by a profile, with thresholds that apply only when the
+/// profile runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileRule {
+    pub rule_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub age_threshold_days: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_threshold_mb: Option<i64>,
+}
+
+/// Profiles as exported to TOML or JSON.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileSet {
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileImport {
+    pub imported: Vec<String>,
+    /// Profiles not imported because one with the same name exists.
+    pub skipped: Vec<String>,
+}
+
+/// A detached pack signature and the public key to trust it with.
+#[derive(Debug, Serialize)]
+pub struct PackSignature {
+    pub signature: String,
+    pub public_key: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ImportOptions {
+    /// Validate and report without writing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Replace existing user rules with the same id instead of skipping them.
+    #[serde(default)]
+    pub replace: bool,
+    /// Accept `tool_call` and high-risk rules from packs not signed by a
+    /// trusted key.
+    #[serde(default)]
+    pub allow_untrusted: bool,
+}
+
+/// A pack rule whose id is already taken.
+#[derive(Debug, Serialize)]
+pub struct PackConflict {
+    pub id: String,
+    pub existing_origin: RuleOrigin,
+    pub replaced: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackRuleError {
+    pub id: String,
+    pub message: String,
 }
 
 #[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub name: String,
+    pub version: String,
+    pub author: Option<String>,
+    /// Name of the trusted key that signed the pack.
+    pub signed_by: Option<String>,
+    pub dry_run: bool,
+    pub imported: Vec<String>,
+    pub conflicts: Vec<PackConflict>,
+    pub invalid: Vec<PackRuleError>,
+}
+
+/// Failing paths kept per report alongside the counters.
+pub const MAX_ERROR_SAMPLES: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathError {
+    pub path: String,
+    /// The `io::ErrorKind` name, e.g. `PermissionDenied`.
+    pub kind: String,
+}
+
+/// Failures hit while walking or cleaning a rule, bucketed by cause.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WalkErrors {
+    pub permission_denied: u64,
+    pub not_found: u64,
+    pub in_use: u64,
+    pub other: u64,
+    pub samples: Vec<PathError>,
+}
+
+impl WalkErrors {
+    pub fn record(&mut self, path: &Path, err: &io::Error) {
+        match err.kind() {
+            io::ErrorKind::PermissionDenied => self.permission_denied += 1,
+            io::ErrorKind::NotFound => self.not_found += 1,
+            io::ErrorKind::ResourceBusy | io::ErrorKind::ExecutableFileBusy => self.in_use += 1,
+            // ERROR_SHARING_VIOLATION / ERROR_LOCK_VIOLATION map to a generic kind.
+            _ if cfg!(target_os = "windows") && matches!(err.raw_os_error(), Some(32) | Some(33)) => {
+                self.in_use += 1
+            }
+            _ => self.other += 1,
+        }
+        if self.samples.len() < MAX_ERROR_SAMPLES {
+            self.samples.push(PathError {
+                path: path.to_string_lossy().into_owned(),
+                kind: format!("{:?}", err.kind()),
+            });
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.permission_denied + self.not_found + self.in_use + self.other
+    }
+}
+
+/// Files a rule also matches but that are attributed to an earlier rule.
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleOverlap {
+    pub rule_id: String,
+    pub file_count: u64,
+    pub total_bytes: u64,
+}
+
+/// Paths the protected-path check refused to remove.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Refusals {
+    pub count: u64,
+    /// `kind` is the refusal reason, e.g. `protected_root`.
+    pub samples: Vec<PathError>,
+}
+
+impl Refusals {
+    pub fn record(&mut self, path: &Path, reason: &str) {
+        self.count += 1;
+        if self.samples.len() < MAX_ERROR_SAMPLES {
+            self.samples.push(PathError {
+                path: path.to_string_lossy().into_owned(),
+                kind: reason.to_string(),
+            });
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct RuleScan {
     pub id: String,
+    /// Logical bytes matched, each hardlinked file counted once.
     pub total_bytes: u64,
+    /// Bytes allocated on disk for the matched files.
+    pub allocated_bytes: u64,
+    /// Allocated bytes freed by removing the matched files; excludes files
+    /// that keep other links outside the selection.
+    pub reclaimable_bytes: u64,
     pub file_count: u64,
-    pub status: String,
+    pub status: RuleStatus,
+    /// False when the scan was cancelled before this rule was fully walked;
+    /// the totals then cover only what was counted so far.
+    pub complete: bool,
     pub blocked: bool,
     pub blocked_reason: Option<String>,
+    pub message: Option<String>,
+    pub errors: WalkErrors,
+    pub overlaps: Vec<RuleOverlap>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct CleanupItemReport {
     pub id: String,
     pub title: String,
     pub category: String,
-    pub risk: String,
+    pub risk: Risk,
     pub total_bytes: u64,
     pub file_count: u64,
-    pub status: String,
+    pub skipped_changed: u64,
+    pub status: RuleStatus,
     pub message: Option<String>,
     pub drive: Option<String>,
+    pub errors: WalkErrors,
+    pub refused: Refusals,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
+pub struct CleanupProgress {
+    pub id: String,
+    pub title: String,
+    pub files: u64,
+    pub bytes: u64,
+    pub total_files: u64,
+    pub total_bytes: u64,
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct CleanupSummary {
     pub total_bytes: u64,
     pub total_files: u64,
@@ -77,7 +559,7 @@ pub struct CleanupSummary {
     pub by_drive: Vec<SummaryBucket>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SummaryBucket {
     pub key: String,
     pub bytes: u64,
@@ -85,8 +567,195 @@ pub struct SummaryBucket {
     pub percent: f64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct CleanupReport {
     pub items: Vec<CleanupItemReport>,
     pub summary: CleanupSummary,
+    pub quarantine_run: Option<String>,
+    /// Set for dry runs: nothing was removed.
+    pub simulated: bool,
+    /// Failures outside any one rule, such as bookkeeping that could not be
+    /// saved after files were already removed.
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
+/// A cleanup kept in the history tables.
+#[derive(Debug, Serialize)]
+pub struct CleanupRunView {
+    pub id: String,
+    pub created_at: u64,
+    pub profile: Option<String>,
+    pub simulated: bool,
+    pub total_bytes: u64,
+    pub total_files: u64,
+    pub quarantine_run: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CleanupRunDetail {
+    pub run: CleanupRunView,
+    pub report: CleanupReport,
+}
+
+/// Space reclaimed over every recorded cleanup, dry runs excluded.
+#[derive(Debug, Serialize)]
+pub struct CleanupStats {
+    pub runs: u64,
+    /// Bytes actually freed: quarantined files count once purged, and never
+    /// if they were restored.
+    pub total_bytes: u64,
+    pub total_files: u64,
+    /// Bytes moved to quarantine that are still held there.
+    pub quarantined_bytes: u64,
+    pub by_category: Vec<SummaryBucket>,
+    pub by_rule: Vec<SummaryBucket>,
+    /// Keyed `YYYY-MM` (UTC), oldest first.
+    pub by_month: Vec<SummaryBucket>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanEntry {
+    pub path: PathBuf,
+    pub size: u64,
+    pub allocated: u64,
+    /// Modification time in nanoseconds since the Unix epoch.
+    pub mtime_ns: Option<u64>,
+    #[serde(skip)]
+    pub file_id: Option<FileId>,
+    #[serde(skip)]
+    pub links: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanRule {
+    pub rule: Rule,
+    pub status: RuleStatus,
+    pub message: Option<String>,
+    /// Directories the rule walked; residue rules prune these once emptied.
+    pub roots: Vec<PathBuf>,
+    pub entries: Vec<PlanEntry>,
+    pub registry_keys: Vec<String>,
+    /// Errors hit while walking the rule to build the plan.
+    pub errors: WalkErrors,
+    /// Matched files left to an earlier rule in the plan.
+    pub overlaps: Vec<RuleOverlap>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CleanupPlan {
+    pub id: String,
+    pub created_at: u64,
+    /// Profile the plan was built from, recorded with the cleanup history.
+    pub profile: Option<String>,
+    pub rules: Vec<PlanRule>,
+    pub excluded: Vec<PathBuf>,
+    /// Rules whose matches changed since the scan the user reviewed.
+    pub drift: Vec<PlanDrift>,
+    /// Rules the plan's profile lists that were left out.
+    pub unavailable: UnavailableRules,
+}
+
+/// A planned rule that matches different files than the last scan showed.
+/// Counts include files left to other rules, so a different selection alone
+/// does not register as drift.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanDrift {
+    pub rule_id: String,
+    pub scanned_files: u64,
+    pub scanned_bytes: u64,
+    pub planned_files: u64,
+    pub planned_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlanRuleSummary {
+    pub id: String,
+    pub status: RuleStatus,
+    pub message: Option<String>,
+    pub total_bytes: u64,
+    pub reclaimable_bytes: u64,
+    pub file_count: u64,
+    pub excluded_count: u64,
+    pub errors: WalkErrors,
+    pub overlaps: Vec<RuleOverlap>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlanSummary {
+    pub id: String,
+    pub created_at: u64,
+    pub total_bytes: u64,
+    pub reclaimable_bytes: u64,
+    pub total_files: u64,
+    pub rules: Vec<PlanRuleSummary>,
+    pub excluded: Vec<PathBuf>,
+    pub drift: Vec<PlanDrift>,
+    pub unavailable: UnavailableRules,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QuarantineRunView {
+    pub id: String,
+    pub created_at: u64,
+    pub status: String,
+    pub total_bytes: u64,
+    pub file_count: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QuarantineItemView {
+    pub id: i64,
+    pub rule_id: String,
+    pub original_path: String,
+    pub size: u64,
+    pub restored: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RestoreReport {
+    pub run_id: String,
+    pub restored: u64,
+    pub skipped_existing: u64,
+    pub failed: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreviewPage {
+    pub session_id: String,
+    pub rule_id: String,
+    pub status: RuleStatus,
+    pub page: usize,
+    pub page_size: usize,
+    pub total_files: u64,
+    pub total_bytes: u64,
+    pub entries: Vec<PlanEntry>,
+    pub errors: WalkErrors,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobView {
+    pub id: String,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    pub result: Option<serde_json::Value>,
+    pub error: Option<ErrorView>,
+}
+
+/// How an `AppError` reaches the frontend.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorView {
+    pub code: &'static str,
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+}
+
+/// An event payload tagged with the job that produced it.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobEvent<T> {
+    pub job_id: String,
+    #[serde(flatten)]
+    pub payload: T,
 }
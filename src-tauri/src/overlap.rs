@@ -0,0 +1,83 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::models::{Rule, RuleOverlap};
+use crate::scan;
+
+/// Attributes every file to the first rule that claims it.
+///
+/// Callers walk rules in `sort_order`, so a file under both `windows_logs` and
+/// `windows_update_logs` belongs to whichever sorts first. Only rules whose
+/// roots nest inside another rule's roots can share files, so only their
+/// paths are remembered.
+pub struct Attribution {
+    tracked: HashSet<String>,
+    owners: HashMap<PathBuf, String>,
+}
+
+impl Attribution {
+    pub fn new(rules: &[&Rule]) -> Self {
+        let roots: Vec<(&str, Vec<PathBuf>)> = rules
+            .iter()
+            .map(|rule| {
+                let roots = scan::rule_roots(rule).iter().map(|root| path_key(root)).collect();
+                (rule.id.as_str(), roots)
+            })
+            .collect();
+        let mut tracked = HashSet::new();
+        for (idx, (id, mine)) in roots.iter().enumerate() {
+            let nested = roots.iter().enumerate().any(|(other, (_, theirs))| {
+                other != idx
+                    && mine
+                        .iter()
+                        .any(|a| theirs.iter().any(|b| a.starts_with(b) || b.starts_with(a)))
+            });
+            if nested {
+                tracked.insert(id.to_string());
+            }
+        }
+        Attribution {
+            tracked,
+            owners: HashMap::new(),
+        }
+    }
+
+    /// Claims `path` for `rule_id`; returns the rule that already owns it, if any.
+    pub fn claim(&mut self, rule_id: &str, path: &Path) -> Option<String> {
+        if !self.tracked.contains(rule_id) {
+            return None;
+        }
+        match self.owners.get(&path_key(path)) {
+            Some(owner) if owner != rule_id => Some(owner.clone()),
+            Some(_) => None,
+            None => {
+                self.owners.insert(path_key(path), rule_id.to_string());
+                None
+            }
+        }
+    }
+}
+
+/// Adds one file to the overlap entry for `owner`.
+pub fn record(overlaps: &mut Vec<RuleOverlap>, owner: String, bytes: u64) {
+    match overlaps.iter_mut().find(|overlap| overlap.rule_id == owner) {
+        Some(overlap) => {
+            overlap.file_count += 1;
+            overlap.total_bytes += bytes;
+        }
+        None => overlaps.push(RuleOverlap {
+            rule_id: owner,
+            file_count: 1,
+            total_bytes: bytes,
+        }),
+    }
+}
+
+/// Windows paths compare case-insensitively.
+pub fn path_key(path: &Path) -> PathBuf {
+    if cfg!(target_os = "windows") {
+        PathBuf::from(path.to_string_lossy().to_lowercase())
+    } else {
+        path.to_path_buf()
+    }
+}
@@ -0,0 +1,258 @@
+use std::collections::HashSet;
+
+use rusqlite::Connection;
+
+use crate::error::{AppError, AppResult};
+use crate::models::{
+    Action, ImportOptions, ImportReport, PackConflict, PackFormat, PackRule, PackRuleError, Risk, Rule, RuleOrigin,
+    RulePack,
+};
+use crate::rules;
+use crate::signing;
+use crate::sources::{self, SourceDirs};
+use crate::tags;
+
+/// Builds a pack from `rule_ids`, or from every user rule when none are given.
+pub fn export_pack(
+    conn: &Connection,
+    name: &str,
+    version: &str,
+    author: Option<String>,
+    rule_ids: Option<&[String]>,
+) -> AppResult<RulePack> {
+    let all = rules::list_all_rules(conn)?;
+    let selected: Vec<Rule> = match rule_ids {
+        Some(ids) => {
+            if let Some(missing) = ids.iter().find(|id| all.iter().all(|rule| &rule.id != *id)) {
+                return Err(AppError::not_found("rule", missing.as_str()));
+            }
+            all.into_iter().filter(|rule| ids.contains(&rule.id)).collect()
+        }
+        None => all.into_iter().filter(|rule| rule.origin == RuleOrigin::User).collect(),
+    };
+    let mut pack = RulePack {
+        name: name.to_string(),
+        version: version.to_string(),
+        author,
+        rules: Vec::new(),
+    };
+    for rule in selected {
+        pack.rules.push(pack_rule(rule));
+    }
+    Ok(pack)
+}
+
+pub fn write_pack(pack: &RulePack, format: PackFormat) -> AppResult<String> {
+    match format {
+        PackFormat::Json => Ok(serde_json::to_string_pretty(pack)?),
+        PackFormat::Toml => toml::to_string_pretty(pack).map_err(|err| AppError::Internal(err.to_string())),
+    }
+}
+
+/// Parses a pack; `format` defaults to JSON for text starting with `{`.
+pub fn read_pack(text: &str, format: Option<PackFormat>) -> AppResult<RulePack> {
+    let format = format.unwrap_or_else(|| detect_format(text));
+    let pack: RulePack = match format {
+        PackFormat::Json => serde_json::from_str(text).map_err(|err| invalid_pack(err.to_string()))?,
+        PackFormat::Toml => toml::from_str(text).map_err(|err| invalid_pack(err.to_string()))?,
+    };
+    if pack.name.trim().is_empty() || pack.version.trim().is_empty() {
+        return Err(invalid_pack("pack name and version are required".to_string()));
+    }
+    Ok(pack)
+}
+
+/// JSON for text starting with `{`, TOML otherwise.
+pub fn detect_format(text: &str) -> PackFormat {
+    if text.trim_start().starts_with('{') {
+        PackFormat::Json
+    } else {
+        PackFormat::Toml
+    }
+}
+
+/// Parses, verifies and imports pack text.
+///
+/// `signature` is a detached signature over `contents`; the pack is trusted
+/// only if it verifies against a key in the trusted keys setting. Untrusted
+/// packs may not carry `tool_call` or high-risk rules unless
+/// `allow_untrusted` is set, since those run shell commands or clean system
+/// locations.
+pub fn import_text(
+    conn: &Connection,
+    dirs: &SourceDirs,
+    contents: &str,
+    signature: Option<&str>,
+    format: Option<PackFormat>,
+    options: &ImportOptions,
+) -> AppResult<ImportReport> {
+    let pack = read_pack(contents, format)?;
+    let trusted = signing::load_trusted_keys(conn)?;
+    let signer = match signature {
+        Some(signature) => signing::verify(contents, signature, &trusted)?.map(|key| key.name.clone()),
+        None => None,
+    };
+    if signer.is_none() && !options.allow_untrusted {
+        let rule_ids: Vec<String> = pack
+            .rules
+            .iter()
+            .filter(|rule| needs_signature(rule.action, rule.risk))
+            .map(|rule| rule.id.clone())
+            .collect();
+        if !rule_ids.is_empty() {
+            return Err(AppError::UntrustedPack { rule_ids });
+        }
+    }
+    import_pack(conn, dirs, pack, signer, options)
+}
+
+/// Validates every rule of a pack and adds the valid ones as user rules.
+///
+/// Rules go through the same checks as rules created in the app. A rule whose
+/// id is taken by any source is reported as a conflict; with `replace` it
+/// overwrites an existing user rule, but built-in and pack rules are never
+/// replaced. Nothing is written when `dry_run` is set.
+pub fn import_pack(
+    conn: &Connection,
+    dirs: &SourceDirs,
+    pack: RulePack,
+    signed_by: Option<String>,
+    options: &ImportOptions,
+) -> AppResult<ImportReport> {
+    let mut report = ImportReport {
+        name: pack.name,
+        version: pack.version,
+        author: pack.author,
+        signed_by,
+        dry_run: options.dry_run,
+        imported: Vec::new(),
+        conflicts: Vec::new(),
+        invalid: Vec::new(),
+    };
+    let existing: Vec<Rule> = sources::load_rules(conn, dirs)?
+        .rules
+        .into_iter()
+        .map(|effective| effective.rule)
+        .collect();
+    let mut sort_order = existing.iter().map(|rule| rule.sort_order).max().unwrap_or(0);
+    let mut seen = HashSet::new();
+    let tx = conn.unchecked_transaction()?;
+    for entry in pack.rules {
+        let id = entry.id.clone();
+        if !seen.insert(id.clone()) {
+            report.invalid.push(rule_error(&id, "duplicate id in pack".to_string()));
+            continue;
+        }
+        if !valid_id(&id) {
+            report.invalid.push(rule_error(&id, "id may only contain letters, digits, '_', '-' and '.'".to_string()));
+            continue;
+        }
+        let current = existing.iter().find(|rule| rule.id == id);
+        if let Some(current) = current {
+            let replaced = options.replace && current.origin == RuleOrigin::User;
+            report.conflicts.push(PackConflict {
+                id: id.clone(),
+                existing_origin: current.origin,
+                replaced,
+            });
+            if !replaced {
+                continue;
+            }
+        }
+        let rule = match current {
+            Some(current) => rule_from_pack(entry, RuleOrigin::User, current.enabled, current.sort_order),
+            None => {
+                sort_order += 10;
+                rule_from_pack(entry, RuleOrigin::User, true, sort_order)
+            }
+        };
+        if let Err(err) = rules::validate_rule(&tx, &rule) {
+            report.invalid.push(rule_error(&id, err.to_string()));
+            continue;
+        }
+        if !options.dry_run {
+            if current.is_some() {
+                tx.execute("DELETE FROM rules WHERE id = ?1", [&id])?;
+            }
+            rules::insert_rule(&tx, &rule)?;
+            rules::set_rule_tags(&tx, &id, &rule.tags)?;
+        }
+        report.imported.push(id);
+    }
+    if !options.dry_run {
+        tx.commit()?;
+    }
+    Ok(report)
+}
+
+fn pack_rule(rule: Rule) -> PackRule {
+    PackRule {
+        id: rule.id,
+        title: rule.title,
+        description: rule.description,
+        category: rule.category,
+        risk: rule.risk,
+        default_checked: rule.default_checked,
+        requires_admin: rule.requires_admin,
+        rule_type: rule.rule_type,
+        scope: rule.scope,
+        path: rule.path,
+        pattern: rule.pattern,
+        size_threshold_mb: rule.size_threshold_mb,
+        age_threshold_days: rule.age_threshold_days,
+        action: rule.action,
+        tool_cmd: rule.tool_cmd,
+        notes: rule.notes,
+        tags: rule.tags,
+    }
+}
+
+pub fn rule_from_pack(entry: PackRule, origin: RuleOrigin, enabled: bool, sort_order: i64) -> Rule {
+    Rule {
+        id: entry.id,
+        title: entry.title.trim().to_string(),
+        description: entry.description.trim().to_string(),
+        category: entry.category.trim().to_string(),
+        risk: entry.risk,
+        default_checked: entry.default_checked,
+        requires_admin: entry.requires_admin,
+        rule_type: entry.rule_type,
+        scope: entry.scope,
+        path: entry.path,
+        pattern: entry.pattern,
+        size_threshold_mb: entry.size_threshold_mb,
+        age_threshold_days: entry.age_threshold_days,
+        action: entry.action,
+        tool_cmd: entry.tool_cmd,
+        enabled,
+        sort_order,
+        notes: entry.notes,
+        origin,
+        tags: entry.tags.iter().map(|tag| tags::normalize(tag)).collect(),
+    }
+}
+
+/// Whether a rule from an unsigned pack is refused: it runs a shell command
+/// or cleans a system location.
+pub fn needs_signature(action: Action, risk: Risk) -> bool {
+    action == Action::ToolCall || risk == Risk::High
+}
+
+pub fn valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 64
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+fn rule_error(id: &str, message: String) -> PackRuleError {
+    PackRuleError {
+        id: id.to_string(),
+        message,
+    }
+}
+
+fn invalid_pack(message: String) -> AppError {
+    AppError::InvalidInput(format!("Invalid rule pack: {}", message))
+}
@@ -0,0 +1,160 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use thiserror::Error;
+
+/// Why a rule path could not be resolved to a safe absolute path.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PathError {
+    #[error("Unresolved environment variable %{0}%")]
+    UnresolvedVariable(String),
+    #[error("Path is not absolute: {0}")]
+    NotAbsolute(String),
+    #[error("Path contains '..': {0}")]
+    ParentTraversal(String),
+}
+
+/// Expands `%VAR%` references and checks that the result is an absolute path
+/// without `..` components.
+///
+/// Unlike a lenient expansion, an unknown variable is an error: leaving
+/// `%VAR%` in place would yield a relative path resolved against the working
+/// directory. `%%` is a literal percent sign, as is a `%` with no closing one.
+pub fn resolve_path(raw: &str) -> Result<PathBuf, PathError> {
+    let expanded = expand_env(raw)?;
+    let path = PathBuf::from(&expanded);
+    if !path.is_absolute() {
+        return Err(PathError::NotAbsolute(expanded));
+    }
+    if path.components().any(|component| component == Component::ParentDir) {
+        return Err(PathError::ParentTraversal(expanded));
+    }
+    Ok(path)
+}
+
+/// Canonicalizes an existing directory or file, following any links in it.
+///
+/// On Windows the `\\?\` prefix `fs::canonicalize` adds to drive paths is
+/// dropped again so paths stay comparable with the ones users type.
+pub fn canonical(path: &Path) -> io::Result<PathBuf> {
+    Ok(strip_verbatim(&fs::canonicalize(path)?))
+}
+
+/// Drops the `\\?\` prefix of a Windows drive path, so `\\?\C:\x` and
+/// `C:\x` compare equal. Verbatim UNC paths, and every path elsewhere, are
+/// returned as they are.
+pub fn strip_verbatim(path: &Path) -> PathBuf {
+    if cfg!(target_os = "windows") {
+        let text = path.to_string_lossy();
+        if let Some(rest) = text.strip_prefix(r"\\?\") {
+            if rest.as_bytes().get(1) == Some(&b':') {
+                return PathBuf::from(rest);
+            }
+        }
+    }
+    path.to_path_buf()
+}
+
+fn expand_env(input: &str) -> Result<String, PathError> {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find('%') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('%') else {
+            out.push_str(&rest[start..]);
+            return Ok(out);
+        };
+        let name = &after[..end];
+        if name.is_empty() {
+            out.push('%');
+        } else if name.contains(['/', '\\']) {
+            // Not a variable reference, just two percent signs in a path.
+            out.push('%');
+            out.push_str(name);
+            rest = &after[end..];
+            continue;
+        } else {
+            let value = env::var(name).map_err(|_| PathError::UnresolvedVariable(name.to_string()))?;
+            out.push_str(&value);
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> PathBuf {
+        env::temp_dir().join("cdiskcleaner-base")
+    }
+
+    #[test]
+    fn expands_variables() {
+        env::set_var("CDISKCLEANER_TEST_BASE", base());
+        assert_eq!(resolve_path("%CDISKCLEANER_TEST_BASE%").unwrap(), base());
+        let nested = format!("%CDISKCLEANER_TEST_BASE%{}cache", std::path::MAIN_SEPARATOR);
+        assert_eq!(resolve_path(&nested).unwrap(), base().join("cache"));
+    }
+
+    #[test]
+    fn keeps_literal_percent_signs() {
+        let base = base();
+        let base = base.to_str().unwrap();
+        assert_eq!(
+            resolve_path(&format!("{}%%100", base)).unwrap(),
+            PathBuf::from(format!("{}%100", base))
+        );
+        assert_eq!(
+            resolve_path(&format!("{}%50", base)).unwrap(),
+            PathBuf::from(format!("{}%50", base))
+        );
+        let sep = std::path::MAIN_SEPARATOR;
+        let spaced = format!("{}{}a%b{}c%d", base, sep, sep);
+        assert_eq!(resolve_path(&spaced).unwrap(), PathBuf::from(&spaced));
+    }
+
+    #[test]
+    fn refuses_unresolved_variables() {
+        assert_eq!(
+            resolve_path("%CDISKCLEANER_TEST_UNSET%"),
+            Err(PathError::UnresolvedVariable("CDISKCLEANER_TEST_UNSET".to_string()))
+        );
+    }
+
+    #[test]
+    fn refuses_relative_and_parent_paths() {
+        assert_eq!(resolve_path("cache"), Err(PathError::NotAbsolute("cache".to_string())));
+        let parent = format!("{}{}..", base().display(), std::path::MAIN_SEPARATOR);
+        assert_eq!(resolve_path(&parent), Err(PathError::ParentTraversal(parent.clone())));
+    }
+
+    #[test]
+    fn canonical_paths_have_no_verbatim_prefix() {
+        let canonical = canonical(&env::temp_dir()).unwrap();
+        assert!(!canonical.to_string_lossy().starts_with(r"\\?\"));
+        assert!(canonical.is_absolute());
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn strips_verbatim_drive_prefixes() {
+        assert_eq!(strip_verbatim(Path::new(r"\\?\C:\Temp")), PathBuf::from(r"C:\Temp"));
+        assert_eq!(
+            strip_verbatim(Path::new(r"\\?\UNC\server\share")),
+            PathBuf::from(r"\\?\UNC\server\share")
+        );
+        assert_eq!(strip_verbatim(Path::new(r"C:\Temp")), PathBuf::from(r"C:\Temp"));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn leaves_paths_alone_elsewhere() {
+        assert_eq!(strip_verbatim(Path::new(r"\\?\C:\Temp")), PathBuf::from(r"\\?\C:\Temp"));
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::cleanup;
+use crate::compile;
+use crate::ids::new_id;
+use crate::models::{
+    CleanupPlan, PlanDrift, PlanEntry, PlanRule, PlanRuleSummary, PlanSummary, Rule, RuleOverlap, RuleScan, RuleStatus,
+    RuleType, UnavailableRules, WalkErrors,
+};
+use crate::overlap::{self, path_key, Attribution};
+use crate::paths;
+use crate::scan::{self, ScanOptions};
+use crate::sizes::{self, FileSize, SizeTally};
+
+pub fn mtime_ns(meta: &Metadata) -> Option<u64> {
+    meta.modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .and_then(|d| d.as_nanos().try_into().ok())
+}
+
+pub fn plan_entry(path: &Path, meta: &Metadata) -> PlanEntry {
+    let size = sizes::file_size(path, meta);
+    PlanEntry {
+        path: path.to_path_buf(),
+        size: size.logical,
+        allocated: size.allocated,
+        mtime_ns: mtime_ns(meta),
+        file_id: size.id,
+        links: size.links,
+    }
+}
+
+fn entry_size(entry: &PlanEntry) -> FileSize {
+    FileSize {
+        logical: entry.size,
+        allocated: entry.allocated,
+        id: entry.file_id,
+        links: entry.links,
+    }
+}
+
+/// Walks the selected rules once and records every file they would remove.
+pub fn build_plan(rules: &[Rule], selected_ids: &[String], options: &ScanOptions) -> CleanupPlan {
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut selected: Vec<&Rule> = rules.iter().filter(|rule| selected_ids.contains(&rule.id)).collect();
+    selected.sort_by_key(|rule| rule.sort_order);
+    let mut attribution = Attribution::new(&selected);
+    let rules = selected
+        .into_iter()
+        .map(|rule| plan_rule(rule, options, &mut attribution))
+        .collect();
+    CleanupPlan {
+        id: new_id("plan"),
+        created_at,
+        profile: None,
+        rules,
+        excluded: Vec::new(),
+        drift: Vec::new(),
+        unavailable: UnavailableRules::default(),
+    }
+}
+
+/// Records in `plan.drift` every rule whose matched files differ from the
+/// completed scan in `scans`, keyed by rule id. Rules that were not scanned,
+/// or whose scan or plan walk did not finish, are not compared.
+pub fn compare_with_scan(plan: &mut CleanupPlan, scans: &HashMap<String, RuleScan>) {
+    plan.drift = plan
+        .rules
+        .iter()
+        .filter(|planned| planned.status == RuleStatus::Ok)
+        .filter_map(|planned| {
+            let scan = scans
+                .get(&planned.rule.id)
+                .filter(|scan| scan.status == RuleStatus::Ok && scan.complete)?;
+            let mut tally = SizeTally::new();
+            let (planned_files, planned_bytes) = matched(
+                planned.entries.len() as u64,
+                planned.entries.iter().map(|entry| tally.add(&entry_size(entry)).logical).sum(),
+                &planned.overlaps,
+            );
+            let (scanned_files, scanned_bytes) = matched(scan.file_count, scan.total_bytes, &scan.overlaps);
+            (planned_files != scanned_files || planned_bytes != scanned_bytes).then(|| PlanDrift {
+                rule_id: planned.rule.id.clone(),
+                scanned_files,
+                scanned_bytes,
+                planned_files,
+                planned_bytes,
+            })
+        })
+        .collect();
+}
+
+fn matched(files: u64, bytes: u64, overlaps: &[RuleOverlap]) -> (u64, u64) {
+    overlaps.iter().fold((files, bytes), |(files, bytes), overlap| {
+        (files + overlap.file_count, bytes + overlap.total_bytes)
+    })
+}
+
+fn plan_rule(rule: &Rule, options: &ScanOptions, attribution: &mut Attribution) -> PlanRule {
+    let mut planned = PlanRule {
+        rule: rule.clone(),
+        status: RuleStatus::Ok,
+        message: None,
+        roots: Vec::new(),
+        entries: Vec::new(),
+        registry_keys: Vec::new(),
+        errors: WalkErrors::default(),
+        overlaps: Vec::new(),
+    };
+    if rule.requires_admin && !options.is_admin {
+        planned.status = RuleStatus::Blocked;
+        planned.message = Some("Requires administrator privileges".to_string());
+        return planned;
+    }
+    let compiled = match compile::compile_rule(rule) {
+        Ok(compiled) => compiled,
+        Err(err) => {
+            planned.status = RuleStatus::Invalid;
+            planned.message = Some(err.to_string());
+            return planned;
+        }
+    };
+
+    match rule.rule_type {
+        RuleType::Special => {}
+        RuleType::Registry => planned.registry_keys = cleanup::registry_orphans(),
+        RuleType::Path | RuleType::Pattern | RuleType::AppResidue => {
+            let mut entries = Vec::new();
+            let mut overlaps = Vec::new();
+            let mut record = |path: &Path, meta: &Metadata| match attribution.claim(&rule.id, path) {
+                Some(owner) => overlap::record(&mut overlaps, owner, meta.len()),
+                None => entries.push(plan_entry(path, meta)),
+            };
+            match scan::walk_rule(&compiled, options, &mut planned.errors, &mut record) {
+                Ok(roots) => planned.roots = roots,
+                Err(skip) => {
+                    planned.status = skip.status();
+                    planned.message = skip.reason().map(str::to_string);
+                }
+            }
+            planned.entries = entries;
+            planned.overlaps = overlaps;
+        }
+    }
+    planned
+}
+
+/// Compares through `path_key`, so on Windows an exclusion typed in any case
+/// still matches the walked paths.
+pub fn is_excluded(plan: &CleanupPlan, path: &Path) -> bool {
+    let path = path_key(path);
+    plan.excluded.iter().any(|excluded| path.starts_with(path_key(excluded)))
+}
+
+/// Excludes files or whole subtrees from a plan before it runs.
+///
+/// Existing paths are canonicalized like the rule bases the plan walked, so
+/// links and differently cased segments still match the planned entries.
+pub fn exclude_paths(plan: &mut CleanupPlan, paths: &[String]) {
+    for path in paths {
+        let path = PathBuf::from(path.trim());
+        let path = paths::canonical(&path).unwrap_or(path);
+        let key = path_key(&path);
+        if !plan.excluded.iter().any(|excluded| path_key(excluded) == key) {
+            plan.excluded.push(path);
+        }
+    }
+}
+
+pub fn summarize_plan(plan: &CleanupPlan) -> PlanSummary {
+    let mut tally = SizeTally::new();
+    let mut total_bytes: u64 = 0;
+    let mut reclaimable_bytes: u64 = 0;
+    let mut total_files: u64 = 0;
+    let mut rules = Vec::with_capacity(plan.rules.len());
+    for planned in &plan.rules {
+        let mut rule_bytes: u64 = 0;
+        let mut rule_reclaimable: u64 = 0;
+        let mut rule_files: u64 = 0;
+        let mut excluded_count: u64 = 0;
+        for entry in &planned.entries {
+            if is_excluded(plan, &entry.path) {
+                excluded_count += 1;
+                continue;
+            }
+            let counted = tally.add(&entry_size(entry));
+            rule_bytes += counted.logical;
+            rule_reclaimable += counted.reclaimable;
+            rule_files += 1;
+        }
+        total_bytes += rule_bytes;
+        reclaimable_bytes += rule_reclaimable;
+        total_files += rule_files;
+        rules.push(PlanRuleSummary {
+            id: planned.rule.id.clone(),
+            status: planned.status,
+            message: planned.message.clone(),
+            total_bytes: rule_bytes,
+            reclaimable_bytes: rule_reclaimable,
+            file_count: rule_files,
+            excluded_count,
+            errors: planned.errors.clone(),
+            overlaps: planned.overlaps.clone(),
+        });
+    }
+    PlanSummary {
+        id: plan.id.clone(),
+        created_at: plan.created_at,
+        total_bytes,
+        reclaimable_bytes,
+        total_files,
+        rules,
+        excluded: plan.excluded.clone(),
+        drift: plan.drift.clone(),
+        unavailable: plan.unavailable.clone(),
+    }
+}
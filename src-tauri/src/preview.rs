@@ -0,0 +1,147 @@
+use std::collections::VecDeque;
+use std::fs::Metadata;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::compile;
+use crate::error::{AppError, AppResult};
+use crate::ids;
+use crate::models::{PlanEntry, PreviewPage, Rule, RuleStatus, WalkErrors};
+use crate::plan;
+use crate::scan::{self, ScanOptions};
+
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+pub const MAX_PAGE_SIZE: usize = 500;
+/// Sessions kept at once, so ones the UI never discards do not pile up.
+const MAX_SESSIONS: usize = 4;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewSort {
+    /// Largest files first.
+    #[default]
+    Size,
+    /// Oldest files first.
+    Age,
+    Path,
+}
+
+/// Refuses what a scan would skip: admin-only rules without elevation and
+/// rules that fail validation.
+pub fn check_rule(rule: &Rule, is_admin: bool) -> AppResult<()> {
+    if rule.requires_admin && !is_admin {
+        return Err(AppError::RequiresAdmin);
+    }
+    compile::compile_rule(rule).map_err(|source| AppError::InvalidRule {
+        rule_id: rule.id.clone(),
+        source,
+    })?;
+    Ok(())
+}
+
+/// The files one rule matched, walked once and paged on request.
+#[derive(Debug, Clone)]
+pub struct PreviewSession {
+    pub id: String,
+    pub rule_id: String,
+    pub status: RuleStatus,
+    /// Entries that could not be read, returned with every page so a
+    /// permission-denied tree does not just look empty.
+    pub errors: WalkErrors,
+    entries: Vec<PlanEntry>,
+    sorted: Option<PreviewSort>,
+}
+
+/// Open preview sessions, oldest first.
+#[derive(Debug, Default)]
+pub struct PreviewSessions {
+    sessions: VecDeque<PreviewSession>,
+}
+
+impl PreviewSessions {
+    /// Adds a session, dropping an older one of the same rule and the oldest
+    /// ones beyond `MAX_SESSIONS`.
+    pub fn insert(&mut self, session: PreviewSession) {
+        self.sessions.retain(|open| open.rule_id != session.rule_id);
+        self.sessions.push_back(session);
+        while self.sessions.len() > MAX_SESSIONS {
+            self.sessions.pop_front();
+        }
+    }
+
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut PreviewSession> {
+        self.sessions.iter_mut().find(|session| session.id == id)
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        self.sessions.retain(|session| session.id != id);
+    }
+}
+
+/// Walks `rule` for a preview session; stops early once `options.cancel`
+/// is set, leaving the session `cancelled` with the files found so far.
+pub fn walk_preview(rule: &Rule, options: &ScanOptions) -> PreviewSession {
+    let mut entries = Vec::new();
+    let mut errors = WalkErrors::default();
+    let mut record = |path: &Path, meta: &Metadata| entries.push(plan::plan_entry(path, meta));
+    let status = match compile::compile_rule(rule) {
+        Ok(compiled) => match scan::walk_rule(&compiled, options, &mut errors, &mut record) {
+            Ok(_) => RuleStatus::Ok,
+            Err(skip) => skip.status(),
+        },
+        Err(_) => RuleStatus::Invalid,
+    };
+    PreviewSession {
+        id: ids::new_id("preview"),
+        rule_id: rule.id.clone(),
+        status,
+        errors,
+        entries,
+        sorted: None,
+    }
+}
+
+/// One page of a session's files; re-sorts only when `sort` changes.
+pub fn page(session: &mut PreviewSession, sort: PreviewSort, page: usize, page_size: Option<usize>) -> PreviewPage {
+    let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    if session.sorted != Some(sort) {
+        let entries = &mut session.entries;
+        match sort {
+            PreviewSort::Size => entries.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path))),
+            PreviewSort::Age => entries.sort_by(|a, b| a.mtime_ns.cmp(&b.mtime_ns).then_with(|| a.path.cmp(&b.path))),
+            PreviewSort::Path => entries.sort_by(|a, b| a.path.cmp(&b.path)),
+        }
+        session.sorted = Some(sort);
+    }
+
+    let entries = session
+        .entries
+        .iter()
+        .skip(page.saturating_mul(page_size))
+        .take(page_size)
+        .cloned()
+        .collect();
+    PreviewPage {
+        session_id: session.id.clone(),
+        rule_id: session.rule_id.clone(),
+        status: session.status,
+        page,
+        page_size,
+        total_files: session.entries.len() as u64,
+        total_bytes: session.entries.iter().map(|entry| entry.size).sum(),
+        entries,
+        errors: session.errors.clone(),
+    }
+}
+
+/// Walks `rule` and returns one page, for callers that need a single page.
+pub fn preview_rule(
+    rule: &Rule,
+    options: &ScanOptions,
+    sort: PreviewSort,
+    page_index: usize,
+    page_size: Option<usize>,
+) -> PreviewPage {
+    page(&mut walk_preview(rule, options), sort, page_index, page_size)
+}
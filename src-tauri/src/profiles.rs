@@ -0,0 +1,317 @@
+use std::collections::HashSet;
+
+use rusqlite::{Connection, OptionalExtension};
+
+use crate::error::{AppError, AppResult};
+use crate::models::{PackFormat, Profile, ProfileImport, ProfileRule, ProfileSet, Rule, UnavailableRules};
+use crate::packs;
+use crate::sources::{self, SourceDirs};
+use crate::tags::{self, TagExpr};
+
+/// Meta key set once the shipped profiles have been added, so profiles the
+/// user deletes stay deleted.
+const DEFAULTS_KEY: &str = "default_profiles";
+
+const DEFAULT_PROFILES: &[(&str, &str, &str)] = &[
+    ("Quick", "Low-risk rules that are checked by default", "default & low-risk"),
+    ("Deep", "Every rule except high-risk ones", "!high-risk"),
+];
+
+pub fn ensure_default_profiles(conn: &Connection) -> rusqlite::Result<()> {
+    let added: Option<String> = conn
+        .query_row("SELECT value FROM meta WHERE key = ?1", [DEFAULTS_KEY], |row| row.get(0))
+        .optional()?;
+    if added.is_some() {
+        return Ok(());
+    }
+    let tx = conn.unchecked_transaction()?;
+    for (index, (name, description, tags)) in DEFAULT_PROFILES.iter().enumerate() {
+        tx.execute(
+            "INSERT OR IGNORE INTO profiles (name, description, tags, sort_order) VALUES (?1, ?2, ?3, ?4)",
+            (name, description, tags, (index as i64 + 1) * 10),
+        )?;
+    }
+    tx.execute("INSERT OR REPLACE INTO meta (key, value) VALUES (?1, '1')", [DEFAULTS_KEY])?;
+    tx.commit()
+}
+
+pub fn list_profiles(conn: &Connection) -> rusqlite::Result<Vec<Profile>> {
+    let mut stmt = conn.prepare("SELECT name, description, tags FROM profiles ORDER BY sort_order, name")?;
+    let rows = stmt.query_map([], profile_from_row)?;
+    let mut profiles = Vec::new();
+    for row in rows {
+        let mut profile = row?;
+        profile.rules = profile_rules(conn, &profile.name)?;
+        profiles.push(profile);
+    }
+    Ok(profiles)
+}
+
+pub fn get_profile(conn: &Connection, name: &str) -> AppResult<Profile> {
+    let mut profile = conn
+        .query_row(
+            "SELECT name, description, tags FROM profiles WHERE name = ?1",
+            [name],
+            profile_from_row,
+        )
+        .optional()?
+        .ok_or_else(|| AppError::not_found("profile", name))?;
+    profile.rules = profile_rules(conn, name)?;
+    Ok(profile)
+}
+
+/// Creates or replaces a profile, keeping its place in the list.
+pub fn save_profile(conn: &Connection, profile: Profile) -> AppResult<Profile> {
+    let profile = validate_profile(profile)?;
+    let tx = conn.unchecked_transaction()?;
+    write_profile(&tx, &profile)?;
+    tx.commit()?;
+    Ok(profile)
+}
+
+fn write_profile(conn: &Connection, profile: &Profile) -> rusqlite::Result<()> {
+    let existing: Option<i64> = conn
+        .query_row(
+            "SELECT sort_order FROM profiles WHERE name = ?1",
+            [&profile.name],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let sort_order = match existing {
+        Some(sort_order) => sort_order,
+        None => conn.query_row("SELECT COALESCE(MAX(sort_order), 0) + 10 FROM profiles", [], |row| row.get(0))?,
+    };
+    conn.execute(
+        "INSERT OR REPLACE INTO profiles (name, description, tags, sort_order) VALUES (?1, ?2, ?3, ?4)",
+        (&profile.name, &profile.description, &profile.tags, sort_order),
+    )?;
+    conn.execute("DELETE FROM profile_rules WHERE profile = ?1", [&profile.name])?;
+    for entry in &profile.rules {
+        conn.execute(
+            "INSERT INTO profile_rules (profile, rule_id, age_threshold_days, size_threshold_mb)
+             VALUES (?1, ?2, ?3, ?4)",
+            (
+                &profile.name,
+                &entry.rule_id,
+                entry.age_threshold_days,
+                entry.size_threshold_mb,
+            ),
+        )?;
+    }
+    Ok(())
+}
+
+pub fn delete_profile(conn: &Connection, name: &str) -> AppResult<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM profile_rules WHERE profile = ?1", [name])?;
+    if tx.execute("DELETE FROM profiles WHERE name = ?1", [name])? == 0 {
+        return Err(AppError::not_found("profile", name));
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Serializes `names` (default: every profile).
+pub fn export_profiles(conn: &Connection, names: Option<&[String]>, format: PackFormat) -> AppResult<String> {
+    let all = list_profiles(conn)?;
+    let profiles = match names {
+        Some(names) => {
+            if let Some(missing) = names.iter().find(|name| all.iter().all(|profile| &profile.name != *name)) {
+                return Err(AppError::not_found("profile", missing.as_str()));
+            }
+            all.into_iter().filter(|profile| names.contains(&profile.name)).collect()
+        }
+        None => all,
+    };
+    let set = ProfileSet { profiles };
+    match format {
+        PackFormat::Json => Ok(serde_json::to_string_pretty(&set)?),
+        PackFormat::Toml => toml::to_string_pretty(&set).map_err(|err| AppError::Internal(err.to_string())),
+    }
+}
+
+/// Imports every profile in `text`. Profiles whose name is taken are skipped
+/// unless `replace` is set. Nothing is written if any profile is invalid.
+pub fn import_profiles(
+    conn: &Connection,
+    text: &str,
+    format: Option<PackFormat>,
+    replace: bool,
+) -> AppResult<ProfileImport> {
+    let invalid = |message: String| AppError::InvalidInput(format!("Invalid profiles: {}", message));
+    let set: ProfileSet = match format.unwrap_or_else(|| packs::detect_format(text)) {
+        PackFormat::Json => serde_json::from_str(text).map_err(|err| invalid(err.to_string()))?,
+        PackFormat::Toml => toml::from_str(text).map_err(|err| invalid(err.to_string()))?,
+    };
+    let profiles = set
+        .profiles
+        .into_iter()
+        .map(validate_profile)
+        .collect::<AppResult<Vec<_>>>()?;
+    let mut names = HashSet::new();
+    if let Some(duplicate) = profiles.iter().find(|profile| !names.insert(profile.name.clone())) {
+        return Err(invalid(format!("duplicate profile {}", duplicate.name)));
+    }
+    let existing: HashSet<String> = list_profiles(conn)?.into_iter().map(|profile| profile.name).collect();
+    let mut report = ProfileImport {
+        imported: Vec::new(),
+        skipped: Vec::new(),
+    };
+    let tx = conn.unchecked_transaction()?;
+    for profile in profiles {
+        if existing.contains(&profile.name) && !replace {
+            report.skipped.push(profile.name);
+            continue;
+        }
+        write_profile(&tx, &profile)?;
+        report.imported.push(profile.name);
+    }
+    tx.commit()?;
+    Ok(report)
+}
+
+/// Enabled rules and the ids picked from them to run.
+#[derive(Debug)]
+pub struct Selection {
+    pub rules: Vec<Rule>,
+    pub selected: Vec<String>,
+    /// Ids a profile lists that are unknown or disabled; callers warn about
+    /// them rather than quietly running fewer rules.
+    pub unavailable: UnavailableRules,
+}
+
+/// The rules a profile runs, with its threshold overrides applied to them.
+///
+/// `rules` are all loaded rules, enabled or not, so that listed rules that
+/// are disabled can be told apart from ids no rule has.
+pub fn apply_profile(profile: &Profile, rules: Vec<Rule>) -> AppResult<Selection> {
+    let expr = match &profile.tags {
+        Some(tags) => Some(TagExpr::parse(tags).map_err(|err| AppError::InvalidInput(err.to_string()))?),
+        None => None,
+    };
+    let mut unavailable = UnavailableRules::default();
+    for entry in &profile.rules {
+        match rules.iter().find(|rule| rule.id == entry.rule_id) {
+            None => unavailable.unknown.push(entry.rule_id.clone()),
+            Some(rule) if !rule.enabled => unavailable.disabled.push(entry.rule_id.clone()),
+            Some(_) => {}
+        }
+    }
+    let mut rules: Vec<Rule> = rules.into_iter().filter(|rule| rule.enabled).collect();
+    let mut selected = Vec::new();
+    for rule in &mut rules {
+        let entry = profile.rules.iter().find(|entry| entry.rule_id == rule.id);
+        match entry {
+            Some(entry) => {
+                if let Some(days) = entry.age_threshold_days {
+                    rule.age_threshold_days = Some(days);
+                }
+                if let Some(mb) = entry.size_threshold_mb {
+                    rule.size_threshold_mb = Some(mb);
+                }
+            }
+            None if expr.as_ref().is_some_and(|expr| expr.matches(rule)) => {}
+            None => continue,
+        }
+        selected.push(rule.id.clone());
+    }
+    Ok(Selection {
+        rules,
+        selected,
+        unavailable,
+    })
+}
+
+/// Enabled rules and the ids to run, picked by a profile or by `selected_ids`
+/// and/or a tag expression.
+pub fn select_rules(
+    conn: &Connection,
+    dirs: &SourceDirs,
+    profile: Option<&str>,
+    selected_ids: Option<Vec<String>>,
+    tags: Option<&str>,
+) -> AppResult<Selection> {
+    match profile {
+        Some(_) if selected_ids.is_some() || tags.is_some() => Err(AppError::InvalidInput(
+            "A profile cannot be combined with selected_ids or tags".to_string(),
+        )),
+        Some(name) => {
+            let rules = sources::load_rules(conn, dirs)?
+                .rules
+                .into_iter()
+                .map(|effective| effective.rule)
+                .collect();
+            apply_profile(&get_profile(conn, name)?, rules)
+        }
+        None => {
+            let rules = sources::enabled_rules(conn, dirs)?;
+            let selected = tags::select(&rules, selected_ids, tags)?;
+            Ok(Selection {
+                rules,
+                selected,
+                unavailable: UnavailableRules::default(),
+            })
+        }
+    }
+}
+
+fn validate_profile(profile: Profile) -> AppResult<Profile> {
+    let name = profile.name.trim().to_string();
+    let invalid = |message: String| AppError::InvalidInput(format!("Profile {}: {}", name, message));
+    if name.is_empty() || name.len() > 64 {
+        return Err(invalid("name must be 1 to 64 characters".to_string()));
+    }
+    let tags = profile
+        .tags
+        .as_deref()
+        .map(str::trim)
+        .filter(|tags| !tags.is_empty())
+        .map(str::to_string);
+    if let Some(tags) = &tags {
+        TagExpr::parse(tags).map_err(|err| invalid(err.to_string()))?;
+    }
+    let mut seen = HashSet::new();
+    for entry in &profile.rules {
+        if !seen.insert(entry.rule_id.as_str()) {
+            return Err(invalid(format!("rule {} is listed twice", entry.rule_id)));
+        }
+        for (field, value) in [
+            ("age_threshold_days", entry.age_threshold_days),
+            ("size_threshold_mb", entry.size_threshold_mb),
+        ] {
+            if value.is_some_and(|value| value < 0) {
+                return Err(invalid(format!("{} of rule {} is negative", field, entry.rule_id)));
+            }
+        }
+    }
+    Ok(Profile {
+        name,
+        description: profile.description.trim().to_string(),
+        tags,
+        rules: profile.rules,
+    })
+}
+
+fn profile_from_row(row: &rusqlite::Row) -> rusqlite::Result<Profile> {
+    Ok(Profile {
+        name: row.get(0)?,
+        description: row.get(1)?,
+        tags: row.get(2)?,
+        rules: Vec::new(),
+    })
+}
+
+fn profile_rules(conn: &Connection, name: &str) -> rusqlite::Result<Vec<ProfileRule>> {
+    let mut stmt = conn.prepare(
+        "SELECT rule_id, age_threshold_days, size_threshold_mb FROM profile_rules
+         WHERE profile = ?1 ORDER BY rule_id",
+    )?;
+    let rows = stmt.query_map([name], |row| {
+        Ok(ProfileRule {
+            rule_id: row.get(0)?,
+            age_threshold_days: row.get(1)?,
+            size_threshold_mb: row.get(2)?,
+        })
+    })?;
+    rows.collect()
+}
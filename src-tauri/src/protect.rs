@@ -0,0 +1,334 @@
+use std::env;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use rusqlite::Connection;
+
+use crate::error::{AppError, AppResult};
+use crate::models::InvalidProtectedPath;
+use crate::overlap::path_key;
+use crate::paths;
+use crate::settings;
+
+/// Extra paths the user never wants touched, one per line.
+pub const PROTECTED_SETTING: &str = "protected_paths";
+
+/// Why the safety layer refused to remove a path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Refusal {
+    /// Relative or containing `..`; never produced by a clean resolve.
+    Unresolved(PathBuf),
+    /// A protected root itself or one of its ancestors.
+    ProtectedRoot(PathBuf),
+    /// Inside a tree that must never be cleaned.
+    ProtectedTree(PathBuf),
+}
+
+impl fmt::Display for Refusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Refusal::Unresolved(path) => write!(f, "Refusing to remove unresolved path {}", path.display()),
+            Refusal::ProtectedRoot(path) => write!(f, "Refusing to remove protected path {}", path.display()),
+            Refusal::ProtectedTree(path) => write!(f, "Refusing to remove inside protected tree {}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for Refusal {}
+
+impl Refusal {
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Refusal::Unresolved(_) => "unresolved",
+            Refusal::ProtectedRoot(_) => "protected_root",
+            Refusal::ProtectedTree(_) => "protected_tree",
+        }
+    }
+}
+
+/// Paths no cleanup may remove, checked before every delete.
+///
+/// Protected roots (the Windows directory, user profiles, `/`, `/home`, ...)
+/// may have files cleaned below them, but neither they nor their ancestors
+/// can be removed. Protected trees (system binaries and user-configured
+/// paths) are off limits entirely.
+#[derive(Debug, Clone)]
+pub struct ProtectedPaths {
+    roots: Vec<PathBuf>,
+    trees: Vec<PathBuf>,
+    invalid: Vec<InvalidProtectedPath>,
+}
+
+impl Default for ProtectedPaths {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl ProtectedPaths {
+    /// The built-in roots and trees plus `user_trees`.
+    pub fn new(user_trees: Vec<PathBuf>) -> Self {
+        let (roots, mut trees) = builtin();
+        trees.extend(user_trees);
+        ProtectedPaths {
+            roots: roots.iter().map(|path| key(path)).collect(),
+            trees: trees.iter().map(|path| key(path)).collect(),
+            invalid: Vec::new(),
+        }
+    }
+
+    /// The built-in paths plus those in the `protected_paths` setting.
+    /// Entries that do not resolve are kept in `invalid` for callers to report.
+    pub fn load(conn: &Connection) -> rusqlite::Result<Self> {
+        let value = settings::get_setting(conn, PROTECTED_SETTING)?.unwrap_or_default();
+        let (user_trees, invalid) = parse_user_paths(&value);
+        let mut protected = Self::new(user_trees);
+        protected.invalid = invalid;
+        Ok(protected)
+    }
+
+    /// User entries that were ignored because they do not resolve.
+    pub fn invalid(&self) -> &[InvalidProtectedPath] {
+        &self.invalid
+    }
+
+    /// Checks a file or directory about to be removed.
+    pub fn check(&self, path: &Path) -> Result<(), Refusal> {
+        let path = &paths::strip_verbatim(path);
+        if !path.is_absolute() || path.components().any(|c| c == Component::ParentDir) {
+            return Err(Refusal::Unresolved(path.to_path_buf()));
+        }
+        let target = path_key(path);
+        if let Some(tree) = self.trees.iter().find(|tree| target.starts_with(tree)) {
+            return Err(Refusal::ProtectedTree(tree.clone()));
+        }
+        if self.is_root_or_ancestor(&target) {
+            return Err(Refusal::ProtectedRoot(path.to_path_buf()));
+        }
+        Ok(())
+    }
+
+    /// True if a rule may not walk `root` at all: `root` is a protected root
+    /// or one of its ancestors, or lies inside a protected tree. A pattern does
+    /// not make such a base safe, since `**` matches everything below it; rules
+    /// must start from a strict subdirectory of a root. Protected trees below
+    /// `root` are left to `check`, which refuses them file by file.
+    pub fn covers(&self, root: &Path) -> bool {
+        let root_key = key(root);
+        self.roots.iter().any(|protected| protected.starts_with(&root_key))
+            || self.trees.iter().any(|tree| root_key.starts_with(tree))
+    }
+
+    fn is_root_or_ancestor(&self, path_key: &Path) -> bool {
+        self.roots.iter().chain(&self.trees).any(|root| root.starts_with(path_key))
+    }
+}
+
+/// Resolves the `protected_paths` setting, one path per line, splitting off
+/// the entries that do not resolve.
+pub fn parse_user_paths(value: &str) -> (Vec<PathBuf>, Vec<InvalidProtectedPath>) {
+    let mut resolved = Vec::new();
+    let mut invalid = Vec::new();
+    for line in value.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        match paths::resolve_path(line) {
+            Ok(path) => resolved.push(path),
+            Err(err) => invalid.push(InvalidProtectedPath {
+                entry: line.to_string(),
+                message: err.to_string(),
+            }),
+        }
+    }
+    (resolved, invalid)
+}
+
+/// Rejects a `protected_paths` value unless every entry resolves.
+pub fn validate_setting(value: &str) -> AppResult<()> {
+    let (_, invalid) = parse_user_paths(value);
+    if invalid.is_empty() {
+        return Ok(());
+    }
+    let entries: Vec<String> = invalid
+        .iter()
+        .map(|entry| format!("{} ({})", entry.entry, entry.message))
+        .collect();
+    Err(AppError::InvalidInput(format!("Invalid protected paths: {}", entries.join(", "))))
+}
+
+fn key(path: &Path) -> PathBuf {
+    path_key(&paths::canonical(path).unwrap_or_else(|_| paths::strip_verbatim(path)))
+}
+
+fn env_path(name: &str) -> Option<PathBuf> {
+    env::var_os(name).map(PathBuf::from).filter(|path| path.is_absolute())
+}
+
+#[cfg(target_os = "windows")]
+fn builtin() -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let mut roots: Vec<PathBuf> = [
+        "SystemRoot",
+        "WINDIR",
+        "ProgramFiles",
+        "ProgramFiles(x86)",
+        "ProgramData",
+        "USERPROFILE",
+        "PUBLIC",
+        "APPDATA",
+        "LOCALAPPDATA",
+    ]
+    .iter()
+    .filter_map(|name| env_path(name))
+    .collect();
+    if let Some(drive) = env::var("SystemDrive").ok().filter(|drive| !drive.is_empty()) {
+        roots.push(PathBuf::from(format!("{}\\", drive)));
+        roots.push(PathBuf::from(format!("{}\\Users", drive)));
+    }
+    let trees = env_path("SystemRoot")
+        .or_else(|| env_path("WINDIR"))
+        .map(|windir| {
+            ["System32", "SysWOW64", "WinSxS"]
+                .iter()
+                .map(|dir| windir.join(dir))
+                .collect()
+        })
+        .unwrap_or_default();
+    (roots, trees)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn builtin() -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let mut roots: Vec<PathBuf> = ["/", "/home", "/root", "/Users", "/var", "/tmp"]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+    roots.extend(env_path("HOME"));
+    let trees = ["/bin", "/sbin", "/usr", "/lib", "/lib64", "/etc", "/boot", "/System"]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+    (roots, trees)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(target_os = "windows"))]
+    mod unix {
+        use super::*;
+
+        fn protected() -> ProtectedPaths {
+            ProtectedPaths::new(vec![PathBuf::from("/srv/keep/data")])
+        }
+
+        #[test]
+        fn refuses_roots() {
+            let protected = protected();
+            for root in ["/", "/home", "/tmp"] {
+                assert_eq!(
+                    protected.check(Path::new(root)),
+                    Err(Refusal::ProtectedRoot(PathBuf::from(root)))
+                );
+                assert!(protected.covers(Path::new(root)));
+            }
+        }
+
+        #[test]
+        fn refuses_ancestors_of_trees() {
+            let protected = protected();
+            assert_eq!(
+                protected.check(Path::new("/srv/keep")),
+                Err(Refusal::ProtectedRoot(PathBuf::from("/srv/keep")))
+            );
+            // Walking above a tree is allowed; `check` refuses its files.
+            assert!(!protected.covers(Path::new("/srv")));
+        }
+
+        #[test]
+        fn refuses_paths_inside_trees() {
+            let protected = protected();
+            assert_eq!(
+                protected.check(Path::new("/usr/lib/libc.so")),
+                Err(Refusal::ProtectedTree(PathBuf::from("/usr")))
+            );
+            assert_eq!(
+                protected.check(Path::new("/srv/keep/data/report.txt")),
+                Err(Refusal::ProtectedTree(PathBuf::from("/srv/keep/data")))
+            );
+            assert!(protected.covers(Path::new("/srv/keep/data/reports")));
+        }
+
+        #[test]
+        fn refuses_unresolved_paths() {
+            let protected = protected();
+            for path in ["cache/file", "/var/cache/../../usr/bin/ls"] {
+                assert_eq!(
+                    protected.check(Path::new(path)),
+                    Err(Refusal::Unresolved(PathBuf::from(path)))
+                );
+            }
+        }
+
+        #[test]
+        fn allows_paths_below_roots() {
+            let protected = protected();
+            assert_eq!(protected.check(Path::new("/var/cache/cdiskcleaner-test/file")), Ok(()));
+            assert!(!protected.covers(Path::new("/var/cache/cdiskcleaner-test")));
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    mod windows {
+        use super::*;
+
+        fn windir() -> PathBuf {
+            env_path("SystemRoot").expect("SystemRoot is set")
+        }
+
+        fn drive() -> String {
+            env::var("SystemDrive").expect("SystemDrive is set")
+        }
+
+        #[test]
+        fn refuses_roots_and_their_ancestors() {
+            let protected = ProtectedPaths::default();
+            let drive_root = PathBuf::from(format!("{}\\", drive()));
+            assert_eq!(protected.check(&drive_root), Err(Refusal::ProtectedRoot(drive_root.clone())));
+            assert!(protected.covers(&drive_root));
+            assert!(matches!(protected.check(&windir()), Err(Refusal::ProtectedRoot(_))));
+        }
+
+        #[test]
+        fn refuses_paths_inside_trees_in_any_case() {
+            let protected = ProtectedPaths::default();
+            let file = windir().join("System32").join("kernel32.dll");
+            assert!(matches!(protected.check(&file), Err(Refusal::ProtectedTree(_))));
+            let upper = PathBuf::from(file.to_string_lossy().to_uppercase());
+            assert!(matches!(protected.check(&upper), Err(Refusal::ProtectedTree(_))));
+            assert!(protected.covers(&windir().join("SYSTEM32").join("drivers")));
+        }
+
+        #[test]
+        fn refuses_verbatim_paths() {
+            let protected = ProtectedPaths::default();
+            let file = PathBuf::from(format!(r"\\?\{}", windir().join("System32").join("kernel32.dll").display()));
+            assert!(matches!(protected.check(&file), Err(Refusal::ProtectedTree(_))));
+            let root = PathBuf::from(format!(r"\\?\{}\", drive()));
+            assert!(matches!(protected.check(&root), Err(Refusal::ProtectedRoot(_))));
+        }
+
+        #[test]
+        fn refuses_unresolved_paths() {
+            let protected = ProtectedPaths::default();
+            for path in [r"Temp\file", r"C:\Temp\..\Windows\System32"] {
+                assert!(matches!(protected.check(Path::new(path)), Err(Refusal::Unresolved(_))));
+            }
+        }
+
+        #[test]
+        fn allows_paths_below_roots() {
+            let protected = ProtectedPaths::default();
+            let temp = windir().join("Temp").join("cdiskcleaner-test.tmp");
+            assert_eq!(protected.check(&temp), Ok(()));
+        }
+    }
+}
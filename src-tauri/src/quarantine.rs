@@ -0,0 +1,380 @@
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rusqlite::{Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+
+use crate::db;
+use crate::history;
+use crate::ids;
+use crate::models::{QuarantineItemView, QuarantineRunView, RestoreReport};
+use crate::plan;
+use crate::settings;
+
+pub const RETENTION_SETTING: &str = "quarantine_retention_days";
+const DEFAULT_RETENTION_DAYS: u64 = 30;
+
+/// Where quarantined files live: one directory per run under `root`,
+/// indexed by the `quarantine_*` tables in the rules database.
+#[derive(Debug, Clone)]
+pub struct QuarantineStore {
+    pub root: PathBuf,
+    pub db_path: PathBuf,
+}
+
+/// A quarantine run being filled by one cleanup.
+pub struct QuarantineRun {
+    conn: Connection,
+    dir: PathBuf,
+    pub id: String,
+    next_item: u64,
+    total_bytes: u64,
+    file_count: u64,
+}
+
+impl QuarantineRun {
+    pub fn begin(store: &QuarantineStore) -> io::Result<QuarantineRun> {
+        let id = ids::new_id("q");
+        let dir = store.root.join(&id);
+        fs::create_dir_all(&dir)?;
+        let conn = db::open_db(&store.db_path).map_err(io::Error::other)?;
+        conn.execute(
+            "INSERT INTO quarantine_runs (id, created_at, status) VALUES (?1, ?2, 'active')",
+            (&id, unix_now() as i64),
+        )
+        .map_err(io::Error::other)?;
+        Ok(QuarantineRun {
+            conn,
+            dir,
+            id,
+            next_item: 0,
+            total_bytes: 0,
+            file_count: 0,
+        })
+    }
+
+    /// Moves `path` into the run and records where it came from.
+    ///
+    /// The item row is written before the move so a stashed file always has a
+    /// record to restore it from; it is dropped again if the move fails.
+    pub fn stash(&mut self, rule_id: &str, path: &Path, meta: &Metadata) -> io::Result<()> {
+        let checksum = sha256_file(path)?;
+        self.next_item += 1;
+        let stored = self.dir.join(self.next_item.to_string());
+        self.conn
+            .execute(
+                "INSERT INTO quarantine_items (run_id, rule_id, original_path, stored_path, size, mtime_ns, checksum)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                (
+                    &self.id,
+                    rule_id,
+                    path.to_string_lossy(),
+                    stored.to_string_lossy(),
+                    meta.len() as i64,
+                    plan::mtime_ns(meta).map(|ns| ns as i64),
+                    checksum,
+                ),
+            )
+            .map_err(io::Error::other)?;
+        let item_id = self.conn.last_insert_rowid();
+        if let Err(err) = move_file(path, &stored, meta.modified().ok()) {
+            let _ = self.conn.execute("DELETE FROM quarantine_items WHERE id = ?1", [item_id]);
+            return Err(err);
+        }
+        self.total_bytes += meta.len();
+        self.file_count += 1;
+        Ok(())
+    }
+
+    /// Saves the run's totals and marks it `complete`; a run never finished
+    /// stays `active`, which tells an interrupted cleanup apart.
+    pub fn finish(self) -> io::Result<String> {
+        self.conn
+            .execute(
+                "UPDATE quarantine_runs SET status = 'complete', total_bytes = ?2, file_count = ?3 WHERE id = ?1",
+                (&self.id, self.total_bytes as i64, self.file_count as i64),
+            )
+            .map_err(io::Error::other)?;
+        Ok(self.id)
+    }
+}
+
+pub fn list_runs(conn: &Connection) -> rusqlite::Result<Vec<QuarantineRunView>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, created_at, status, total_bytes, file_count
+         FROM quarantine_runs
+         ORDER BY created_at DESC, id DESC",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(QuarantineRunView {
+            id: row.get(0)?,
+            created_at: row.get::<_, i64>(1)? as u64,
+            status: row.get(2)?,
+            total_bytes: row.get::<_, i64>(3)? as u64,
+            file_count: row.get::<_, i64>(4)? as u64,
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn list_items(conn: &Connection, run_id: &str) -> rusqlite::Result<Vec<QuarantineItemView>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, rule_id, original_path, size, restored
+         FROM quarantine_items
+         WHERE run_id = ?1
+         ORDER BY id",
+    )?;
+    let rows = stmt.query_map([run_id], |row| {
+        Ok(QuarantineItemView {
+            id: row.get(0)?,
+            rule_id: row.get(1)?,
+            original_path: row.get(2)?,
+            size: row.get::<_, i64>(3)? as u64,
+            restored: row.get::<_, i64>(4)? != 0,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Moves every file of a run back to its original path.
+///
+/// Files whose original path is occupied again, or whose stored copy no longer
+/// matches the recorded checksum, are left in quarantine.
+pub fn restore_run(conn: &Connection, run_id: &str) -> io::Result<RestoreReport> {
+    let items = pending_items(conn, run_id).map_err(io::Error::other)?;
+    let mut report = RestoreReport {
+        run_id: run_id.to_string(),
+        restored: 0,
+        skipped_existing: 0,
+        failed: 0,
+    };
+    for item in items {
+        let original = PathBuf::from(&item.original_path);
+        if original.exists() {
+            report.skipped_existing += 1;
+            continue;
+        }
+        let stored = PathBuf::from(&item.stored_path);
+        let restored = sha256_file(&stored)
+            .ok()
+            .filter(|checksum| *checksum == item.checksum)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "checksum mismatch"))
+            .and_then(|_| {
+                if let Some(parent) = original.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                move_file(&stored, &original, item.modified)
+            });
+        match restored {
+            Ok(()) => {
+                conn.execute("UPDATE quarantine_items SET restored = 1 WHERE id = ?1", [item.id])
+                    .map_err(io::Error::other)?;
+                history::record_restore(conn, run_id, &item.rule_id, item.size).map_err(io::Error::other)?;
+                report.restored += 1;
+            }
+            Err(_) => report.failed += 1,
+        }
+    }
+    let remaining: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM quarantine_items WHERE run_id = ?1 AND restored = 0",
+            [run_id],
+            |row| row.get(0),
+        )
+        .map_err(io::Error::other)?;
+    if remaining == 0 {
+        conn.execute(
+            "UPDATE quarantine_runs SET status = 'restored' WHERE id = ?1",
+            [run_id],
+        )
+        .map_err(io::Error::other)?;
+    }
+    Ok(report)
+}
+
+/// Permanently deletes a run's stored files and forgets the run.
+pub fn purge_run(conn: &Connection, store: &QuarantineStore, run_id: &str) -> io::Result<()> {
+    let exists: Option<String> = conn
+        .query_row("SELECT id FROM quarantine_runs WHERE id = ?1", [run_id], |row| row.get(0))
+        .optional()
+        .map_err(io::Error::other)?;
+    if exists.is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Unknown quarantine run: {}", run_id),
+        ));
+    }
+    let dir = store.root.join(run_id);
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    conn.execute("DELETE FROM quarantine_items WHERE run_id = ?1", [run_id])
+        .map_err(io::Error::other)?;
+    conn.execute("DELETE FROM quarantine_runs WHERE id = ?1", [run_id])
+        .map_err(io::Error::other)?;
+    Ok(())
+}
+
+pub fn retention_days(conn: &Connection) -> rusqlite::Result<u64> {
+    Ok(settings::get_setting(conn, RETENTION_SETTING)?
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_RETENTION_DAYS))
+}
+
+/// Purges runs older than the configured retention and returns how many were removed.
+pub fn purge_expired(conn: &Connection, store: &QuarantineStore) -> io::Result<usize> {
+    let retention_days = retention_days(conn).map_err(io::Error::other)?;
+    let cutoff = unix_now().saturating_sub(retention_days.saturating_mul(24 * 60 * 60));
+    let expired: Vec<String> = {
+        let mut stmt = conn
+            .prepare("SELECT id FROM quarantine_runs WHERE created_at < ?1")
+            .map_err(io::Error::other)?;
+        let rows = stmt
+            .query_map([cutoff as i64], |row| row.get(0))
+            .map_err(io::Error::other)?;
+        rows.collect::<rusqlite::Result<_>>().map_err(io::Error::other)?
+    };
+    for run_id in &expired {
+        purge_run(conn, store, run_id)?;
+    }
+    Ok(expired.len())
+}
+
+struct PendingItem {
+    id: i64,
+    rule_id: String,
+    size: u64,
+    original_path: String,
+    stored_path: String,
+    checksum: String,
+    modified: Option<SystemTime>,
+}
+
+fn pending_items(conn: &Connection, run_id: &str) -> rusqlite::Result<Vec<PendingItem>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, original_path, stored_path, checksum, mtime_ns, rule_id, size
+         FROM quarantine_items
+         WHERE run_id = ?1 AND restored = 0
+         ORDER BY id",
+    )?;
+    let rows = stmt.query_map([run_id], |row| {
+        let mtime_ns: Option<i64> = row.get(4)?;
+        Ok(PendingItem {
+            id: row.get(0)?,
+            rule_id: row.get(5)?,
+            size: row.get::<_, i64>(6)? as u64,
+            original_path: row.get(1)?,
+            stored_path: row.get(2)?,
+            checksum: row.get(3)?,
+            modified: mtime_ns.map(|ns| UNIX_EPOCH + Duration::from_nanos(ns as u64)),
+        })
+    })?;
+    rows.collect()
+}
+
+/// Renames `from` to `to`, falling back to copy-and-delete across volumes.
+fn move_file(from: &Path, to: &Path, modified: Option<SystemTime>) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    if let Some(modified) = modified {
+        let _ = File::options().write(true).open(to).and_then(|f| f.set_modified(modified));
+    }
+    if let Err(err) = fs::remove_file(from) {
+        let _ = fs::remove_file(to);
+        return Err(err);
+    }
+    Ok(())
+}
+
+fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stash_file(store: &QuarantineStore, path: &Path) -> String {
+        let mut run = QuarantineRun::begin(store).unwrap();
+        run.stash("user_temp", path, &fs::metadata(path).unwrap()).unwrap();
+        run.finish().unwrap()
+    }
+
+    fn run_status(conn: &Connection, run_id: &str) -> Option<String> {
+        list_runs(conn)
+            .unwrap()
+            .into_iter()
+            .find(|run| run.id == run_id)
+            .map(|run| run.status)
+    }
+
+    #[test]
+    fn stash_restore_and_purge_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = db::init_db(dir.path()).unwrap().db_path;
+        let conn = db::open_db(&db_path).unwrap();
+        let store = QuarantineStore {
+            root: dir.path().join("quarantine"),
+            db_path,
+        };
+        let original = dir.path().join("cache").join("file.bin");
+        fs::create_dir_all(original.parent().unwrap()).unwrap();
+        fs::write(&original, b"cached bytes").unwrap();
+
+        let run_id = stash_file(&store, &original);
+        assert!(!original.exists());
+        let run = list_runs(&conn).unwrap().into_iter().find(|run| run.id == run_id).unwrap();
+        assert_eq!((run.status.as_str(), run.total_bytes, run.file_count), ("complete", 12, 1));
+
+        let report = restore_run(&conn, &run_id).unwrap();
+        assert_eq!((report.restored, report.skipped_existing, report.failed), (1, 0, 0));
+        assert_eq!(fs::read(&original).unwrap(), b"cached bytes");
+        assert_eq!(run_status(&conn, &run_id).as_deref(), Some("restored"));
+        assert!(list_items(&conn, &run_id).unwrap().iter().all(|item| item.restored));
+
+        let run_id = stash_file(&store, &original);
+        purge_run(&conn, &store, &run_id).unwrap();
+        assert!(!original.exists());
+        assert!(!store.root.join(&run_id).exists());
+        assert_eq!(run_status(&conn, &run_id), None);
+        assert!(list_items(&conn, &run_id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn unfinished_runs_stay_active() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = db::init_db(dir.path()).unwrap().db_path;
+        let conn = db::open_db(&db_path).unwrap();
+        let store = QuarantineStore {
+            root: dir.path().join("quarantine"),
+            db_path,
+        };
+        let run_id = QuarantineRun::begin(&store).unwrap().id.clone();
+        assert_eq!(run_status(&conn, &run_id).as_deref(), Some("active"));
+    }
+}
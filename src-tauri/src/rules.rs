@@ -1,52 +1,109 @@
-use rusqlite::Connection;
+use std::collections::HashMap;
 
-use crate::models::{Rule, RuleView};
+use rusqlite::types::FromSql;
+use rusqlite::{Connection, OptionalExtension, Row};
 
-pub fn list_rules(conn: &Connection) -> rusqlite::Result<Vec<Rule>> {
-    let mut stmt = conn.prepare(
-        "SELECT id, title, description, category, risk, default_checked, requires_admin,
+use crate::compile::{self, RuleError};
+use crate::error::{AppError, AppResult};
+use crate::ids;
+use crate::models::{Rule, RuleInput, RuleOrigin, RuleTuning, RuleView};
+use crate::protect::ProtectedPaths;
+use crate::scan;
+use crate::seed;
+use crate::sources::{self, SourceDirs};
+use crate::tags;
+
+const RULE_COLUMNS: &str = "id, title, description, category, risk, default_checked, requires_admin,
                 rule_type, scope, path, pattern, size_threshold_mb, age_threshold_days,
-                action, tool_cmd, enabled, sort_order, notes
-         FROM rules
-         WHERE enabled = 1
-         ORDER BY sort_order, category, title",
-    )?;
+                action, tool_cmd, enabled, sort_order, notes, origin";
 
-    let rows = stmt.query_map([], |row| {
-        Ok(Rule {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            description: row.get(2)?,
-            category: row.get(3)?,
-            risk: row.get(4)?,
-            default_checked: row.get::<_, i64>(5)? != 0,
-            requires_admin: row.get::<_, i64>(6)? != 0,
-            rule_type: row.get(7)?,
-            scope: row.get(8)?,
-            path: row.get(9)?,
-            pattern: row.get(10)?,
-            size_threshold_mb: row.get(11)?,
-            age_threshold_days: row.get(12)?,
-            action: row.get(13)?,
-            tool_cmd: row.get(14)?,
-            enabled: row.get::<_, i64>(15)? != 0,
-            sort_order: row.get(16)?,
-            notes: row.get(17)?,
-        })
-    })?;
+/// Enabled and disabled rules alike, as stored in the database. Rows whose
+/// `risk`, `rule_type`, `scope` or `action` is not a known value fail the
+/// whole listing rather than being guessed at.
+pub fn list_all_rules(conn: &Connection) -> rusqlite::Result<Vec<Rule>> {
+    query_rules(conn, "")
+}
+
+pub fn get_rule(conn: &Connection, id: &str) -> AppResult<Rule> {
+    let sql = format!("SELECT {} FROM rules WHERE id = ?1", RULE_COLUMNS);
+    let mut rule = conn
+        .query_row(&sql, [id], rule_from_row)
+        .optional()?
+        .ok_or_else(|| AppError::not_found("rule", id))?;
+    rule.tags = rule_tags(conn, id)?;
+    Ok(rule)
+}
+
+fn query_rules(conn: &Connection, filter: &str) -> rusqlite::Result<Vec<Rule>> {
+    let sql = format!(
+        "SELECT {} FROM rules {} ORDER BY sort_order, category, title",
+        RULE_COLUMNS, filter
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map([], rule_from_row)?;
 
     let mut out = Vec::new();
     for item in rows {
         out.push(item?);
     }
+    let mut stmt = conn.prepare("SELECT rule_id, tag FROM rule_tags ORDER BY tag")?;
+    let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+    for row in stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))? {
+        let (rule_id, tag) = row?;
+        tags.entry(rule_id).or_default().push(tag);
+    }
+    for rule in &mut out {
+        rule.tags = tags.remove(&rule.id).unwrap_or_default();
+    }
     Ok(out)
 }
 
-pub fn list_rules_with_privilege(conn: &Connection, is_admin: bool) -> rusqlite::Result<Vec<RuleView>> {
-    let rules = list_rules(conn)?;
-    let views = rules
+fn rule_from_row(row: &Row) -> rusqlite::Result<Rule> {
+    Ok(Rule {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        description: row.get(2)?,
+        category: row.get(3)?,
+        risk: typed_column(row, 4)?,
+        default_checked: row.get::<_, i64>(5)? != 0,
+        requires_admin: row.get::<_, i64>(6)? != 0,
+        rule_type: typed_column(row, 7)?,
+        scope: typed_column(row, 8)?,
+        path: row.get(9)?,
+        pattern: row.get(10)?,
+        size_threshold_mb: row.get(11)?,
+        age_threshold_days: row.get(12)?,
+        action: typed_column(row, 13)?,
+        tool_cmd: row.get(14)?,
+        enabled: row.get::<_, i64>(15)? != 0,
+        sort_order: row.get(16)?,
+        notes: row.get(17)?,
+        origin: typed_column(row, 18)?,
+        tags: Vec::new(),
+    })
+}
+
+/// Reads an enum column, naming the offending rule if the value is unknown.
+fn typed_column<T: FromSql>(row: &Row, idx: usize) -> rusqlite::Result<T> {
+    row.get(idx).map_err(|err| match err {
+        rusqlite::Error::FromSqlConversionFailure(col, ty, source) => {
+            let id: String = row.get(0).unwrap_or_default();
+            rusqlite::Error::FromSqlConversionFailure(col, ty, format!("rule {}: {}", id, source).into())
+        }
+        other => other,
+    })
+}
+
+/// Effective rules from every source, for the rule list.
+pub fn list_rules_with_privilege(conn: &Connection, dirs: &SourceDirs, is_admin: bool) -> AppResult<Vec<RuleView>> {
+    let loaded = sources::load_rules(conn, dirs)?;
+    let views = loaded
+        .rules
         .into_iter()
-        .map(|rule| {
+        .map(|effective| {
+            let rule = effective.rule;
+            let overridden = sources::overridden_fields(&effective.field_sources);
+            let invalid_reason = compile::compile_rule(&rule).err().map(|err| err.to_string());
             let blocked = rule.requires_admin && !is_admin;
             let blocked_reason = if blocked {
                 Some("Requires administrator privileges".to_string())
@@ -72,10 +129,270 @@ pub fn list_rules_with_privilege(conn: &Connection, is_admin: bool) -> rusqlite:
                 enabled: rule.enabled,
                 sort_order: rule.sort_order,
                 notes: rule.notes,
+                origin: rule.origin,
+                tags: rule.tags,
+                source: effective.source,
+                field_sources: effective.field_sources,
+                overridden,
                 blocked,
                 blocked_reason,
+                invalid_reason,
             }
         })
         .collect();
     Ok(views)
 }
+
+/// Adds a user rule after the existing ones. Its id never collides with a
+/// seed id, so seed upgrades cannot replace it.
+pub fn create_rule(conn: &Connection, input: RuleInput) -> AppResult<Rule> {
+    let sort_order: i64 = conn.query_row("SELECT COALESCE(MAX(sort_order), 0) + 10 FROM rules", [], |row| {
+        row.get(0)
+    })?;
+    let rule = user_rule(ids::new_id("custom"), input, true, sort_order);
+    validate_rule(conn, &rule)?;
+    insert_rule(conn, &rule)?;
+    Ok(rule)
+}
+
+pub fn insert_rule(conn: &Connection, rule: &Rule) -> rusqlite::Result<()> {
+    let sql = format!(
+        "INSERT INTO rules ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
+        RULE_COLUMNS
+    );
+    conn.execute(
+        &sql,
+        rusqlite::params![
+            rule.id,
+            rule.title,
+            rule.description,
+            rule.category,
+            rule.risk,
+            rule.default_checked,
+            rule.requires_admin,
+            rule.rule_type,
+            rule.scope,
+            rule.path,
+            rule.pattern,
+            rule.size_threshold_mb,
+            rule.age_threshold_days,
+            rule.action,
+            rule.tool_cmd,
+            rule.enabled,
+            rule.sort_order,
+            rule.notes,
+            rule.origin,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Replaces the fields of a user rule. Seed rules can only be enabled,
+/// disabled or duplicated.
+pub fn update_rule(conn: &Connection, id: &str, input: RuleInput) -> AppResult<Rule> {
+    let existing = get_rule(conn, id)?;
+    if existing.origin != RuleOrigin::User {
+        return Err(AppError::InvalidInput(format!(
+            "Built-in rule {} cannot be edited; duplicate it instead",
+            id
+        )));
+    }
+    let mut rule = user_rule(existing.id, input, existing.enabled, existing.sort_order);
+    rule.tags = existing.tags;
+    validate_rule(conn, &rule)?;
+    conn.execute(
+        "UPDATE rules SET title = ?2, description = ?3, category = ?4, risk = ?5, default_checked = ?6,
+                requires_admin = ?7, rule_type = ?8, scope = ?9, path = ?10, pattern = ?11,
+                size_threshold_mb = ?12, age_threshold_days = ?13, action = ?14, tool_cmd = ?15, notes = ?16
+         WHERE id = ?1",
+        rusqlite::params![
+            rule.id,
+            rule.title,
+            rule.description,
+            rule.category,
+            rule.risk,
+            rule.default_checked,
+            rule.requires_admin,
+            rule.rule_type,
+            rule.scope,
+            rule.path,
+            rule.pattern,
+            rule.size_threshold_mb,
+            rule.age_threshold_days,
+            rule.action,
+            rule.tool_cmd,
+            rule.notes,
+        ],
+    )?;
+    Ok(rule)
+}
+
+/// Copies any rule, including its tags, into a new user rule.
+pub fn duplicate_rule(conn: &Connection, id: &str) -> AppResult<Rule> {
+    let source = get_rule(conn, id)?;
+    let mut copy = create_rule(
+        conn,
+        RuleInput {
+            title: format!("{} (copy)", source.title),
+            description: source.description,
+            category: source.category,
+            risk: source.risk,
+            default_checked: source.default_checked,
+            requires_admin: source.requires_admin,
+            rule_type: source.rule_type,
+            scope: source.scope,
+            path: source.path,
+            pattern: source.pattern,
+            size_threshold_mb: source.size_threshold_mb,
+            age_threshold_days: source.age_threshold_days,
+            action: source.action,
+            tool_cmd: source.tool_cmd,
+            notes: source.notes,
+        },
+    )?;
+    conn.execute(
+        "INSERT INTO rule_tags (rule_id, tag) SELECT ?1, tag FROM rule_tags WHERE rule_id = ?2",
+        (&copy.id, id),
+    )?;
+    copy.tags = source.tags;
+    Ok(copy)
+}
+
+pub fn set_rule_enabled(conn: &Connection, id: &str, enabled: bool) -> AppResult<()> {
+    let rule = get_rule(conn, id)?;
+    conn.execute("UPDATE rules SET enabled = ?2 WHERE id = ?1", (id, enabled))?;
+    if rule.origin == RuleOrigin::Seed && rule.enabled != enabled {
+        seed::record_override(conn, id, "enabled")?;
+    }
+    Ok(())
+}
+
+/// Changes the tunable fields of any rule. On a seed rule each changed field
+/// is recorded as an override so seed upgrades keep the user's value.
+pub fn tune_rule(conn: &Connection, id: &str, tuning: RuleTuning) -> AppResult<Rule> {
+    let existing = get_rule(conn, id)?;
+    let tuned = Rule {
+        default_checked: tuning.default_checked,
+        action: tuning.action,
+        age_threshold_days: tuning.age_threshold_days,
+        size_threshold_mb: tuning.size_threshold_mb,
+        ..existing.clone()
+    };
+    compile::compile_rule(&tuned).map_err(|source| AppError::InvalidRule {
+        rule_id: id.to_string(),
+        source,
+    })?;
+    let tx = conn.unchecked_transaction()?;
+    for ((field, old), (_, new)) in seed::fields(&existing).into_iter().zip(seed::fields(&tuned)) {
+        if old == new {
+            continue;
+        }
+        tx.execute(&format!("UPDATE rules SET {} = ?2 WHERE id = ?1", field), (id, new))?;
+        if existing.origin == RuleOrigin::Seed {
+            seed::record_override(&tx, id, field)?;
+        }
+    }
+    tx.commit()?;
+    Ok(tuned)
+}
+
+/// Drops a seed rule's overrides and restores its shipped values.
+pub fn reset_rule(conn: &Connection, id: &str) -> AppResult<Rule> {
+    let rule = get_rule(conn, id)?;
+    if rule.origin != RuleOrigin::Seed {
+        return Err(AppError::InvalidInput(format!("Rule {} is not a built-in rule", id)));
+    }
+    seed::reset_overrides(conn, id)?;
+    get_rule(conn, id)
+}
+
+/// Deletes a user rule. Built-in rules come back with the next seed, so they
+/// are disabled instead.
+pub fn delete_rule(conn: &Connection, id: &str) -> AppResult<()> {
+    let rule = get_rule(conn, id)?;
+    if rule.origin != RuleOrigin::User {
+        return Err(AppError::InvalidInput(format!(
+            "Built-in rule {} cannot be deleted; disable it instead",
+            id
+        )));
+    }
+    conn.execute("DELETE FROM rule_tags WHERE rule_id = ?1", [id])?;
+    conn.execute("DELETE FROM rules WHERE id = ?1", [id])?;
+    Ok(())
+}
+
+fn user_rule(id: String, input: RuleInput, enabled: bool, sort_order: i64) -> Rule {
+    Rule {
+        id,
+        title: input.title.trim().to_string(),
+        description: input.description.trim().to_string(),
+        category: input.category.trim().to_string(),
+        risk: input.risk,
+        default_checked: input.default_checked,
+        requires_admin: input.requires_admin,
+        rule_type: input.rule_type,
+        scope: input.scope,
+        path: non_empty(input.path),
+        pattern: non_empty(input.pattern),
+        size_threshold_mb: input.size_threshold_mb,
+        age_threshold_days: input.age_threshold_days,
+        action: input.action,
+        tool_cmd: non_empty(input.tool_cmd),
+        enabled,
+        sort_order,
+        notes: non_empty(input.notes),
+        origin: RuleOrigin::User,
+        tags: Vec::new(),
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+/// The schema's NOT NULL columns, `compile_rule`'s checks, and the protected
+/// paths: a rule may not start at a protected root or one of its ancestors,
+/// with or without a pattern, or reach into a protected tree.
+pub fn validate_rule(conn: &Connection, rule: &Rule) -> AppResult<()> {
+    let invalid = |source: RuleError| AppError::InvalidRule {
+        rule_id: rule.id.clone(),
+        source,
+    };
+    for (field, value) in [
+        ("title", &rule.title),
+        ("description", &rule.description),
+        ("category", &rule.category),
+    ] {
+        if value.is_empty() {
+            return Err(invalid(RuleError::MissingField(field)));
+        }
+    }
+    if let Some(tag) = rule.tags.iter().find(|tag| !tags::valid_tag(tag)) {
+        return Err(invalid(RuleError::InvalidTag(tag.clone())));
+    }
+    compile::compile_rule(rule).map_err(invalid)?;
+    let protected = ProtectedPaths::load(conn)?;
+    for root in scan::rule_roots(rule) {
+        if protected.covers(&root) {
+            return Err(invalid(RuleError::ProtectedPath(root)));
+        }
+    }
+    Ok(())
+}
+
+pub fn rule_tags(conn: &Connection, rule_id: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT tag FROM rule_tags WHERE rule_id = ?1 ORDER BY tag")?;
+    let rows = stmt.query_map([rule_id], |row| row.get(0))?;
+    rows.collect()
+}
+
+pub fn set_rule_tags(conn: &Connection, rule_id: &str, tags: &[String]) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM rule_tags WHERE rule_id = ?1", [rule_id])?;
+    for tag in tags {
+        conn.execute(
+            "INSERT OR IGNORE INTO rule_tags (rule_id, tag) VALUES (?1, ?2)",
+            (rule_id, tag),
+        )?;
+    }
+    Ok(())
+}
@@ -1,209 +1,493 @@
+use std::collections::HashMap;
 use std::env;
+use std::fs::Metadata;
+use std::io;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::slice;
 use std::time::{Duration, SystemTime};
 
 use glob::Pattern;
 use jwalk::WalkDir;
 
-use crate::models::{Rule, RuleScan};
-
-static SCAN_CANCELLED: AtomicBool = AtomicBool::new(false);
+use crate::compile::{self, CompiledRule, RuleError};
+use crate::jobs::CancelToken;
+use crate::models::{Rule, RuleScan, RuleStatus, RuleType, WalkErrors};
+use crate::overlap::{self, Attribution};
+use crate::paths;
+use crate::sizes::{self, Counted, FileSize, SizeTally};
 
 pub struct ScanOptions {
     pub is_admin: bool,
+    pub cancel: CancelToken,
 }
 
-pub fn clear_cancel() {
-    SCAN_CANCELLED.store(false, Ordering::SeqCst);
-}
-
-pub fn request_cancel() {
-    SCAN_CANCELLED.store(true, Ordering::SeqCst);
-}
-
-fn is_cancelled() -> bool {
-    SCAN_CANCELLED.load(Ordering::SeqCst)
-}
-
-fn cancelled_scan(rule: &Rule) -> RuleScan {
+fn empty_scan(rule: &Rule, status: RuleStatus) -> RuleScan {
     RuleScan {
         id: rule.id.clone(),
         total_bytes: 0,
+        allocated_bytes: 0,
+        reclaimable_bytes: 0,
         file_count: 0,
-        status: "cancelled".to_string(),
+        status,
+        complete: true,
         blocked: false,
         blocked_reason: None,
+        message: None,
+        errors: WalkErrors::default(),
+        overlaps: Vec::new(),
+    }
+}
+
+fn invalid_scan(rule: &Rule, err: &RuleError) -> RuleScan {
+    RuleScan {
+        message: Some(err.to_string()),
+        ..empty_scan(rule, RuleStatus::Invalid)
     }
 }
 
+/// A rule the scan never reached.
+fn cancelled_scan(rule: &Rule) -> RuleScan {
+    RuleScan {
+        complete: false,
+        ..empty_scan(rule, RuleStatus::Cancelled)
+    }
+}
+
+/// Scans rules in `sort_order`. Hardlinked files are deduplicated across all of
+/// them (see `SizeTally`) and each file counts towards one rule only (see
+/// `Attribution`). Rules sharing a base directory are walked together, see
+/// `group_rules`.
 pub fn scan_rules<F>(rules: &[Rule], options: &ScanOptions, progress: &mut F) -> Vec<RuleScan>
 where
     F: FnMut(&Rule),
 {
-    let mut results = Vec::with_capacity(rules.len());
-    for (idx, rule) in rules.iter().enumerate() {
-        if is_cancelled() {
-            results.push(cancelled_scan(rule));
-            for rest in rules.iter().skip(idx + 1) {
-                results.push(cancelled_scan(rest));
+    let mut rules: Vec<&Rule> = rules.iter().collect();
+    rules.sort_by_key(|rule| rule.sort_order);
+    let mut attribution = Attribution::new(&rules);
+    let mut tally = SizeTally::new();
+    let mut results: Vec<Option<RuleScan>> = Vec::with_capacity(rules.len());
+    let mut compiled: Vec<Option<CompiledRule>> = Vec::with_capacity(rules.len());
+    for rule in &rules {
+        match compile::compile_rule(rule) {
+            Ok(rule) => {
+                compiled.push(Some(rule));
+                results.push(None);
+            }
+            Err(err) => {
+                compiled.push(None);
+                results.push(Some(invalid_scan(rule, &err)));
+            }
+        }
+    }
+    for group in group_rules(&compiled, options) {
+        if options.cancel.is_cancelled() {
+            break;
+        }
+        for &idx in &group.members {
+            progress(rules[idx]);
+        }
+        match group.base {
+            Some(base) if group.members.len() > 1 => {
+                let members: Vec<&CompiledRule> = group
+                    .members
+                    .iter()
+                    .filter_map(|&idx| compiled[idx].as_ref())
+                    .collect();
+                let scans = scan_group(&members, &base, options, &mut attribution, &mut tally);
+                for (&idx, scan) in group.members.iter().zip(scans) {
+                    results[idx] = Some(scan);
+                }
+            }
+            _ => {
+                for &idx in &group.members {
+                    if let Some(rule) = &compiled[idx] {
+                        results[idx] = Some(scan_rule(rule, options, &mut attribution, &mut tally));
+                    }
+                }
             }
-            return results;
         }
-        progress(rule);
-        results.push(scan_rule(rule, options));
     }
     results
+        .into_iter()
+        .zip(&rules)
+        .map(|(scan, rule)| scan.unwrap_or_else(|| cancelled_scan(rule)))
+        .collect()
+}
+
+struct RuleGroup {
+    base: Option<PathBuf>,
+    /// Indices into the sorted rules, in order.
+    members: Vec<usize>,
+}
+
+/// Groups rules (already in `sort_order`) that can share one walk of their base
+/// directory. Rules that failed to compile (`None`) are left out.
+///
+/// A rule only joins an earlier group if no rule between them walks a root
+/// nested with that base; otherwise attribution of shared files could differ
+/// from walking the rules one by one.
+fn group_rules(rules: &[Option<CompiledRule>], options: &ScanOptions) -> Vec<RuleGroup> {
+    let mut groups: Vec<RuleGroup> = Vec::new();
+    let mut open: HashMap<PathBuf, usize> = HashMap::new();
+    for (idx, rule) in rules.iter().enumerate() {
+        let Some(rule) = rule else { continue };
+        let base = shared_base(rule, options);
+        let key = base.as_deref().map(overlap::path_key);
+        match key.as_ref().and_then(|key| open.get(key)) {
+            Some(&group) => groups[group].members.push(idx),
+            None => {
+                if let Some(key) = &key {
+                    open.insert(key.clone(), groups.len());
+                }
+                groups.push(RuleGroup {
+                    base,
+                    members: vec![idx],
+                });
+            }
+        }
+        let roots: Vec<PathBuf> = rule_roots(&rule.rule).iter().map(|root| overlap::path_key(root)).collect();
+        open.retain(|open_key, _| {
+            Some(open_key) == key.as_ref()
+                || !roots
+                    .iter()
+                    .any(|root| root.starts_with(open_key) || open_key.starts_with(root))
+        });
+    }
+    groups
+}
+
+/// The directory a rule would walk, if it can be walked together with others.
+fn shared_base(rule: &CompiledRule, options: &ScanOptions) -> Option<PathBuf> {
+    if rule.rule.requires_admin && !options.is_admin {
+        return None;
+    }
+    let base = paths::canonical(rule.base.as_deref()?).ok()?;
+    base.is_dir().then_some(base)
+}
+
+/// Scans several rules with one walk of `base`, giving the same results as
+/// `scan_rule` on each of them in turn.
+fn scan_group(
+    members: &[&CompiledRule],
+    base: &Path,
+    options: &ScanOptions,
+    attribution: &mut Attribution,
+    tally: &mut SizeTally,
+) -> Vec<RuleScan> {
+    let filters: Vec<FileFilter> = members.iter().map(|rule| FileFilter::new(rule)).collect();
+    let mut scans: Vec<RuleScan> = members.iter().map(|rule| empty_scan(&rule.rule, RuleStatus::Ok)).collect();
+    let mut errors = vec![WalkErrors::default(); members.len()];
+    // Hardlinked files are tallied per rule after the walk, so the rule that
+    // sees the first and last link is the same as in a rule-by-rule scan.
+    let mut linked: Vec<Vec<FileSize>> = vec![Vec::new(); members.len()];
+    let finished = walk_directory(
+        base,
+        SystemTime::now(),
+        &filters,
+        &options.cancel,
+        &mut errors,
+        &mut |idx, path, meta| {
+            let scan = &mut scans[idx];
+            if let Some(owner) = attribution.claim(&members[idx].rule.id, path) {
+                overlap::record(&mut scan.overlaps, owner, meta.len());
+                return;
+            }
+            scan.file_count += 1;
+            let size = sizes::file_size(path, meta);
+            if size.is_linked() {
+                linked[idx].push(size);
+            } else {
+                add_counted(scan, tally.add(&size));
+            }
+        },
+    );
+    for ((scan, linked), errors) in scans.iter_mut().zip(linked).zip(errors) {
+        for size in &linked {
+            add_counted(scan, tally.add(size));
+        }
+        scan.errors = errors;
+        if !finished {
+            scan.status = RuleStatus::Cancelled;
+            scan.complete = false;
+        }
+    }
+    scans
+}
+
+fn add_counted(scan: &mut RuleScan, counted: Counted) {
+    scan.total_bytes += counted.logical;
+    scan.allocated_bytes += counted.allocated;
+    scan.reclaimable_bytes += counted.reclaimable;
 }
 
-fn scan_rule(rule: &Rule, options: &ScanOptions) -> RuleScan {
-    if is_cancelled() {
+fn scan_rule(
+    compiled: &CompiledRule,
+    options: &ScanOptions,
+    attribution: &mut Attribution,
+    tally: &mut SizeTally,
+) -> RuleScan {
+    let rule = &compiled.rule;
+    if options.cancel.is_cancelled() {
         return cancelled_scan(rule);
     }
+
+    let mut scan = empty_scan(rule, RuleStatus::Ok);
+    let mut count = |path: &Path, meta: &Metadata| {
+        if let Some(owner) = attribution.claim(&rule.id, path) {
+            overlap::record(&mut scan.overlaps, owner, meta.len());
+            return;
+        }
+        add_counted(&mut scan, tally.add(&sizes::file_size(path, meta)));
+        scan.file_count += 1;
+    };
+    let mut errors = WalkErrors::default();
+    let walked = walk_rule(compiled, options, &mut errors, &mut count);
+    scan.errors = errors;
+
+    match walked {
+        Ok(_) => scan,
+        // Keep whatever was counted before the walk stopped.
+        Err(WalkSkip::Cancelled) => RuleScan {
+            status: RuleStatus::Cancelled,
+            complete: false,
+            ..scan
+        },
+        Err(skip) => RuleScan {
+            blocked: skip == WalkSkip::Blocked,
+            blocked_reason: skip.reason().map(str::to_string),
+            errors: scan.errors,
+            ..empty_scan(rule, skip.status())
+        },
+    }
+}
+
+/// Why a rule's walk produced no (or only a partial) result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkSkip {
+    Blocked,
+    Unsupported,
+    MissingPath,
+    Missing,
+    Cancelled,
+}
+
+impl WalkSkip {
+    pub fn status(self) -> RuleStatus {
+        match self {
+            WalkSkip::Blocked => RuleStatus::Blocked,
+            WalkSkip::Unsupported => RuleStatus::Unsupported,
+            WalkSkip::MissingPath => RuleStatus::MissingPath,
+            WalkSkip::Missing => RuleStatus::Missing,
+            WalkSkip::Cancelled => RuleStatus::Cancelled,
+        }
+    }
+
+    pub fn reason(self) -> Option<&'static str> {
+        match self {
+            WalkSkip::Blocked => Some("Requires administrator privileges"),
+            _ => None,
+        }
+    }
+}
+
+/// Walks every file a rule matches and hands it to `visit`.
+///
+/// Scan totals, cleanup plans and previews all go through here, so they agree
+/// on exactly which files a rule covers. Returns the directories that were
+/// walked (or the single file for file-valued `path` rules), or
+/// `WalkSkip::Cancelled` once the walk stopped early; files visited before
+/// that point have already been passed to `visit`. Entries that could not be
+/// read are tallied in `errors` instead of being visited.
+pub fn walk_rule<F>(
+    compiled: &CompiledRule,
+    options: &ScanOptions,
+    errors: &mut WalkErrors,
+    visit: &mut F,
+) -> Result<Vec<PathBuf>, WalkSkip>
+where
+    F: FnMut(&Path, &Metadata),
+{
+    let rule = &compiled.rule;
     if rule.requires_admin && !options.is_admin {
-        return RuleScan {
-            id: rule.id.clone(),
-            total_bytes: 0,
-            file_count: 0,
-            status: "blocked".to_string(),
-            blocked: true,
-            blocked_reason: Some("Requires administrator privileges".to_string()),
-        };
+        return Err(WalkSkip::Blocked);
     }
 
-    if rule.rule_type != "path" && rule.rule_type != "pattern" && rule.rule_type != "app_residue" {
-        return RuleScan {
-            id: rule.id.clone(),
-            total_bytes: 0,
-            file_count: 0,
-            status: "unsupported".to_string(),
-            blocked: false,
-            blocked_reason: None,
-        };
+    if !matches!(rule.rule_type, RuleType::Path | RuleType::Pattern | RuleType::AppResidue) {
+        return Err(WalkSkip::Unsupported);
     }
 
     let now = SystemTime::now();
-    let age_threshold = rule
-        .age_threshold_days
-        .and_then(|days| days.try_into().ok())
-        .map(|days: u64| Duration::from_secs(days * 24 * 60 * 60));
-    let size_threshold = rule
-        .size_threshold_mb
-        .and_then(|mb| mb.try_into().ok())
-        .map(|mb: u64| mb * 1024 * 1024);
-
-    let mut total_bytes: u64 = 0;
-    let mut file_count: u64 = 0;
-
-    if rule.rule_type == "app_residue" {
+    if rule.rule_type == RuleType::AppResidue {
         let candidates = residue_candidates(rule.age_threshold_days);
-        for dir in candidates {
-            if is_cancelled() {
-                return cancelled_scan(rule);
+        let filters = [FileFilter::default()];
+        for dir in &candidates {
+            let walked = walk_directory(dir, now, &filters, &options.cancel, slice::from_mut(errors), &mut |_, path, meta| {
+                visit(path, meta)
+            });
+            if !walked {
+                return Err(WalkSkip::Cancelled);
             }
-            let (bytes, files) = scan_directory(&dir, now, age_threshold, size_threshold, None);
-            total_bytes += bytes;
-            file_count += files;
         }
-    } else {
-        let base = match &rule.path {
-            Some(path) => expand_percent_env(path),
-            None => {
-                return RuleScan {
-                    id: rule.id.clone(),
-                    total_bytes: 0,
-                    file_count: 0,
-                    status: "missing_path".to_string(),
-                    blocked: false,
-                    blocked_reason: None,
-                }
+        return Ok(candidates);
+    }
+
+    let base = compiled.base.as_deref().ok_or(WalkSkip::MissingPath)?;
+    let base_path = match paths::canonical(base) {
+        Ok(path) => path,
+        Err(err) => {
+            if err.kind() != io::ErrorKind::NotFound {
+                errors.record(base, &err);
             }
-        };
-        let base_path = PathBuf::from(base);
-        if !base_path.exists() {
-            return RuleScan {
-                id: rule.id.clone(),
-                total_bytes: 0,
-                file_count: 0,
-                status: "missing".to_string(),
-                blocked: false,
-                blocked_reason: None,
-            };
+            return Err(WalkSkip::Missing);
         }
-        if rule.rule_type == "path" && base_path.is_file() {
-            if let Ok(meta) = base_path.metadata() {
-                if should_count(&meta, now, age_threshold, size_threshold) {
-                    total_bytes += meta.len();
-                    file_count += 1;
+    };
+    let filter = FileFilter::new(compiled);
+    if rule.rule_type == RuleType::Path && base_path.is_file() {
+        match base_path.metadata() {
+            Ok(meta) => {
+                if filter.should_count(&meta, now) {
+                    visit(&base_path, &meta);
                 }
             }
-        } else {
-            let pattern = rule.pattern.as_deref().map(normalize_pattern);
-            let matcher = pattern
-                .as_deref()
-                .and_then(|p| Pattern::new(p).ok());
-
-            let (bytes, files) = scan_directory(&base_path, now, age_threshold, size_threshold, matcher.as_ref());
-            total_bytes += bytes;
-            file_count += files;
+            Err(err) => errors.record(&base_path, &err),
+        }
+    } else {
+        let finished = walk_directory(
+            &base_path,
+            now,
+            slice::from_ref(&filter),
+            &options.cancel,
+            slice::from_mut(errors),
+            &mut |_, path, meta| visit(path, meta),
+        );
+        if !finished {
+            return Err(WalkSkip::Cancelled);
         }
     }
+    Ok(vec![base_path])
+}
 
-    RuleScan {
-        id: rule.id.clone(),
-        total_bytes,
-        file_count,
-        status: "ok".to_string(),
-        blocked: false,
-        blocked_reason: None,
+/// A rule's pattern and thresholds, applied to each file of a walk.
+#[derive(Default)]
+struct FileFilter {
+    matcher: Option<Pattern>,
+    age_threshold: Option<Duration>,
+    size_threshold: Option<u64>,
+}
+
+impl FileFilter {
+    fn new(rule: &CompiledRule) -> FileFilter {
+        FileFilter {
+            matcher: rule.matcher.clone(),
+            age_threshold: rule.age_threshold,
+            size_threshold: rule.size_threshold,
+        }
+    }
+
+    fn matches_path(&self, base_path: &Path, path: &Path) -> bool {
+        match (&self.matcher, path.strip_prefix(base_path)) {
+            (Some(matcher), Ok(rel)) => matcher.matches(&normalize_path(rel)),
+            _ => true,
+        }
+    }
+
+    fn should_count(&self, meta: &Metadata, now: SystemTime) -> bool {
+        should_count(meta, now, self.age_threshold, self.size_threshold)
     }
 }
 
-fn scan_directory(
+/// Walks `base_path` once and hands each file to every filter that accepts it,
+/// as `visit(filter_index, path, meta)`.
+///
+/// Errors are recorded against every filter, except metadata errors, which
+/// only count for filters whose pattern matched the path. Returns false if the
+/// walk was cut short by cancellation.
+fn walk_directory<F>(
     base_path: &Path,
     now: SystemTime,
-    age_threshold: Option<Duration>,
-    size_threshold: Option<u64>,
-    matcher: Option<&Pattern>,
-) -> (u64, u64) {
-    let mut total_bytes: u64 = 0;
-    let mut file_count: u64 = 0;
+    filters: &[FileFilter],
+    cancel: &CancelToken,
+    errors: &mut [WalkErrors],
+    visit: &mut F,
+) -> bool
+where
+    F: FnMut(usize, &Path, &Metadata),
+{
+    let mut hits = Vec::with_capacity(filters.len());
     for entry in WalkDir::new(base_path)
         .follow_links(false)
         .into_iter()
     {
-        if is_cancelled() {
-            break;
+        if cancel.is_cancelled() {
+            return false;
         }
         let entry = match entry {
             Ok(entry) => entry,
-            Err(_) => continue,
+            Err(err) => {
+                let (path, err) = walk_error(base_path, err);
+                errors.iter_mut().for_each(|errors| errors.record(&path, &err));
+                continue;
+            }
         };
         if !entry.file_type().is_file() {
             continue;
         }
-        if let Some(matcher) = matcher {
-            if let Some(rel) = entry.path().strip_prefix(base_path).ok() {
-                let rel_norm = normalize_path(rel);
-                if !matcher.matches(&rel_norm) {
-                    continue;
-                }
-            }
+        let path = entry.path();
+        hits.clear();
+        hits.extend((0..filters.len()).filter(|&idx| filters[idx].matches_path(base_path, &path)));
+        if hits.is_empty() {
+            continue;
         }
         let meta = match entry.metadata() {
             Ok(meta) => meta,
-            Err(_) => continue,
+            Err(err) => {
+                let (path, err) = walk_error(&path, err);
+                hits.iter().for_each(|&idx| errors[idx].record(&path, &err));
+                continue;
+            }
         };
-        if !should_count(&meta, now, age_threshold, size_threshold) {
-            continue;
+        for &idx in &hits {
+            if filters[idx].should_count(&meta, now) {
+                visit(idx, &path, &meta);
+            }
         }
-        total_bytes += meta.len();
-        file_count += 1;
     }
-    (total_bytes, file_count)
+    true
+}
+
+fn walk_error(fallback: &Path, err: jwalk::Error) -> (PathBuf, io::Error) {
+    let path = err.path().unwrap_or(fallback).to_path_buf();
+    let err = err.into_io_error().unwrap_or_else(|| io::Error::other("filesystem loop"));
+    (path, err)
+}
+
+/// The directories (or file) a rule would walk, without walking them.
+pub fn rule_roots(rule: &Rule) -> Vec<PathBuf> {
+    match rule.rule_type {
+        RuleType::AppResidue => residue_candidates(rule.age_threshold_days),
+        RuleType::Path | RuleType::Pattern => rule
+            .path
+            .as_deref()
+            .and_then(|path| paths::resolve_path(path).ok())
+            .map(|path| vec![paths::canonical(&path).unwrap_or(path)])
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
 }
 
-fn residue_candidates(age_threshold_days: Option<i64>) -> Vec<PathBuf> {
+/// Top-level folders of the program and app data directories that belong to
+/// no installed program and have not changed in `age_threshold_days` (180 by
+/// default). A negative or overflowing threshold matches nothing; callers
+/// that skip `compile::compile_rule` can still pass one.
+pub fn residue_candidates(age_threshold_days: Option<i64>) -> Vec<PathBuf> {
+    let cutoff = age_threshold_days.unwrap_or(180);
+    let Some(cutoff_duration) = u64::try_from(cutoff)
+        .ok()
+        .and_then(|days| days.checked_mul(24 * 60 * 60))
+        .map(Duration::from_secs)
+    else {
+        return Vec::new();
+    };
     let mut roots: Vec<PathBuf> = Vec::new();
     let env_keys = [
         "ProgramFiles",
@@ -214,11 +498,12 @@ fn residue_candidates(age_threshold_days: Option<i64>) -> Vec<PathBuf> {
     ];
     for key in env_keys {
         if let Ok(val) = env::var(key) {
-            roots.push(PathBuf::from(val));
+            let root = PathBuf::from(val);
+            if root.is_absolute() {
+                roots.push(root);
+            }
         }
     }
-    let cutoff = age_threshold_days.unwrap_or(180);
-    let cutoff_duration = Duration::from_secs(cutoff as u64 * 24 * 60 * 60);
     let now = SystemTime::now();
     let installed = installed_paths();
 
@@ -289,7 +574,7 @@ fn installed_paths() -> Vec<PathBuf> {
 }
 
 fn should_count(
-    meta: &std::fs::Metadata,
+    meta: &Metadata,
     now: SystemTime,
     age_threshold: Option<Duration>,
     size_threshold: Option<u64>,
@@ -311,41 +596,100 @@ fn should_count(
     true
 }
 
-fn expand_percent_env(input: &str) -> String {
-    let mut out = String::with_capacity(input.len());
-    let mut chars = input.chars().peekable();
-    while let Some(ch) = chars.next() {
-        if ch == '%' {
-            let mut var = String::new();
-            while let Some(&next) = chars.peek() {
-                chars.next();
-                if next == '%' {
-                    break;
-                }
-                var.push(next);
-            }
-            if var.is_empty() {
-                out.push('%');
-                continue;
-            }
-            if let Ok(val) = env::var(&var) {
-                out.push_str(&val);
-            } else {
-                out.push('%');
-                out.push_str(&var);
-                out.push('%');
-            }
-        } else {
-            out.push(ch);
-        }
-    }
-    out
-}
-
 fn normalize_path(path: &Path) -> String {
     path.to_string_lossy().replace('\\', "/")
 }
 
-fn normalize_pattern(pattern: &str) -> String {
-    pattern.replace('\\', "/")
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+
+    use super::*;
+    use crate::models::{Action, Risk, RuleOrigin, Scope};
+
+    fn rule(id: &str, base: &Path, pattern: Option<&str>, sort_order: i64) -> Rule {
+        Rule {
+            id: id.to_string(),
+            title: id.to_string(),
+            description: "Test rule".to_string(),
+            category: "cache".to_string(),
+            risk: Risk::Low,
+            default_checked: true,
+            requires_admin: false,
+            rule_type: if pattern.is_some() { RuleType::Pattern } else { RuleType::Path },
+            scope: Scope::User,
+            path: Some(base.to_string_lossy().into_owned()),
+            pattern: pattern.map(str::to_string),
+            size_threshold_mb: None,
+            age_threshold_days: None,
+            action: Action::Delete,
+            tool_cmd: None,
+            enabled: true,
+            sort_order,
+            notes: None,
+            origin: RuleOrigin::User,
+            tags: Vec::new(),
+        }
+    }
+
+    fn write(path: &Path, len: usize, age_days: u64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![0u8; len]).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(age_days * 24 * 60 * 60);
+        File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    #[test]
+    fn grouped_scan_matches_rule_by_rule_walks() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("cache");
+        write(&base.join("a.log"), 100, 0);
+        write(&base.join("old.log"), 200, 10);
+        write(&base.join("sub").join("b.tmp"), 300, 0);
+        write(&base.join("sub").join("big.bin"), 2 * 1024 * 1024, 0);
+        write(&base.join("sub").join("deep").join("c.tmp"), 400, 10);
+        write(&base.join("sub").join("notes.txt"), 500, 0);
+        #[cfg(unix)]
+        fs::hard_link(base.join("sub").join("b.tmp"), base.join("b-link.log")).unwrap();
+
+        let mut old_logs = rule("old_logs", &base, Some("*.log"), 10);
+        old_logs.age_threshold_days = Some(5);
+        let mut big = rule("big", &base, None, 30);
+        big.size_threshold_mb = Some(1);
+        let rules = vec![
+            old_logs,
+            rule("tmp", &base, Some("**/*.tmp"), 20),
+            big,
+            rule("logs", &base, Some("*.log"), 40),
+            rule("everything", &base, None, 50),
+        ];
+        let options = ScanOptions {
+            is_admin: false,
+            cancel: CancelToken::new(),
+        };
+
+        let compiled: Vec<Option<CompiledRule>> = rules.iter().map(|rule| compile::compile_rule(rule).ok()).collect();
+        let groups = group_rules(&compiled, &options);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].members.len(), rules.len());
+        let grouped = scan_rules(&rules, &options, &mut |_| {});
+
+        let sorted: Vec<&Rule> = rules.iter().collect();
+        let mut attribution = Attribution::new(&sorted);
+        let mut tally = SizeTally::new();
+        let separate: Vec<RuleScan> = rules
+            .iter()
+            .map(|rule| {
+                let compiled = compile::compile_rule(rule).unwrap();
+                scan_rule(&compiled, &options, &mut attribution, &mut tally)
+            })
+            .collect();
+
+        assert_eq!(
+            serde_json::to_value(&grouped).unwrap(),
+            serde_json::to_value(&separate).unwrap()
+        );
+        let everything = &grouped[4];
+        assert!(everything.file_count > 0 && !everything.overlaps.is_empty());
+    }
 }
@@ -0,0 +1,173 @@
+use std::collections::{HashMap, HashSet};
+
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension};
+
+use crate::db;
+use crate::models::{Rule, RuleChange, RuleOrigin, SeedUpgrade};
+use crate::rules;
+
+const SEED_SQL: &str = include_str!("../../data/seed.sql");
+
+/// Meta key holding the report of the last seed upgrade until it is dismissed.
+const UPGRADE_KEY: &str = "seed_upgrade";
+
+/// Seed rules as shipped, read by running `seed.sql` against a scratch
+/// in-memory database.
+pub fn load_seed() -> rusqlite::Result<(String, Vec<Rule>)> {
+    let conn = Connection::open_in_memory()?;
+    conn.execute_batch(db::SCHEMA_SQL)?;
+    conn.execute_batch(SEED_SQL)?;
+    let version = conn.query_row("SELECT value FROM meta WHERE key = 'seed_version'", [], |row| row.get(0))?;
+    Ok((version, rules::list_all_rules(&conn)?))
+}
+
+/// Brings the seed rules in `conn` up to the shipped seed.
+///
+/// New seed values are merged underneath user overrides: a field recorded in
+/// `rule_overrides` keeps its current value, every other field takes the new
+/// one. Seed rules no longer shipped are removed; user rules are never
+/// touched. On an upgrade (as opposed to a first install) the report is kept
+/// in `meta` for `last_seed_upgrade`.
+pub fn apply_seed(conn: &Connection, previous: Option<&str>) -> rusqlite::Result<Option<SeedUpgrade>> {
+    let (version, seed) = load_seed()?;
+    if previous == Some(version.as_str()) {
+        return Ok(None);
+    }
+    let (existing, user): (Vec<Rule>, Vec<Rule>) = rules::list_all_rules(conn)?
+        .into_iter()
+        .partition(|rule| rule.origin == RuleOrigin::Seed);
+    let existing: HashMap<String, Rule> = existing.into_iter().map(|rule| (rule.id.clone(), rule)).collect();
+    let user_ids: HashSet<String> = user.into_iter().map(|rule| rule.id).collect();
+    let mut upgrade = SeedUpgrade {
+        from_version: previous.map(str::to_string),
+        to_version: version.clone(),
+        added: Vec::new(),
+        changed: Vec::new(),
+        removed: Vec::new(),
+        shadowed: Vec::new(),
+    };
+
+    let tx = conn.unchecked_transaction()?;
+    for rule in &seed {
+        if user_ids.contains(&rule.id) {
+            upgrade.shadowed.push(rule.id.clone());
+            continue;
+        }
+        let Some(current) = existing.get(&rule.id) else {
+            rules::insert_rule(&tx, rule)?;
+            upgrade.added.push(rule.id.clone());
+            continue;
+        };
+        let overridden = overrides(&tx, &rule.id)?;
+        let mut change = RuleChange {
+            id: rule.id.clone(),
+            fields: Vec::new(),
+            kept_overrides: Vec::new(),
+        };
+        for ((field, old), (_, new)) in fields(current).into_iter().zip(fields(rule)) {
+            if old == new {
+                continue;
+            }
+            if overridden.contains(field) {
+                change.kept_overrides.push(field.to_string());
+            } else {
+                tx.execute(&format!("UPDATE rules SET {} = ?2 WHERE id = ?1", field), (&rule.id, new))?;
+                change.fields.push(field.to_string());
+            }
+        }
+        if !change.fields.is_empty() || !change.kept_overrides.is_empty() {
+            upgrade.changed.push(change);
+        }
+    }
+    let shipped: HashSet<&str> = seed.iter().map(|rule| rule.id.as_str()).collect();
+    for id in existing.keys().filter(|id| !shipped.contains(id.as_str())) {
+        tx.execute("DELETE FROM rule_overrides WHERE rule_id = ?1", [id])?;
+        tx.execute("DELETE FROM rule_tags WHERE rule_id = ?1", [id])?;
+        tx.execute("DELETE FROM rules WHERE id = ?1", [id])?;
+        upgrade.removed.push(id.clone());
+    }
+    upgrade.removed.sort();
+    tx.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES ('seed_version', ?1)",
+        [&version],
+    )?;
+    if previous.is_some() {
+        let report = serde_json::to_string(&upgrade).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        tx.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+            (UPGRADE_KEY, report),
+        )?;
+    }
+    tx.commit()?;
+    Ok(previous.map(|_| upgrade))
+}
+
+/// The report of the last seed upgrade, until `dismiss_seed_upgrade`.
+pub fn last_seed_upgrade(conn: &Connection) -> rusqlite::Result<Option<SeedUpgrade>> {
+    let report: Option<String> = conn
+        .query_row("SELECT value FROM meta WHERE key = ?1", [UPGRADE_KEY], |row| row.get(0))
+        .optional()?;
+    Ok(report.and_then(|report| serde_json::from_str(&report).ok()))
+}
+
+pub fn dismiss_seed_upgrade(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM meta WHERE key = ?1", [UPGRADE_KEY])?;
+    Ok(())
+}
+
+/// Fields of a seed rule the user has changed from the shipped value.
+pub fn overrides(conn: &Connection, rule_id: &str) -> rusqlite::Result<HashSet<String>> {
+    let mut stmt = conn.prepare("SELECT field FROM rule_overrides WHERE rule_id = ?1")?;
+    let rows = stmt.query_map([rule_id], |row| row.get(0))?;
+    rows.collect()
+}
+
+pub fn record_override(conn: &Connection, rule_id: &str, field: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO rule_overrides (rule_id, field) VALUES (?1, ?2)",
+        (rule_id, field),
+    )?;
+    Ok(())
+}
+
+/// Puts a seed rule's overridden fields back to their shipped values.
+pub fn reset_overrides(conn: &Connection, rule_id: &str) -> rusqlite::Result<()> {
+    let (_, seed) = load_seed()?;
+    let overridden = overrides(conn, rule_id)?;
+    let tx = conn.unchecked_transaction()?;
+    if let Some(rule) = seed.iter().find(|rule| rule.id == rule_id) {
+        for (field, value) in fields(rule) {
+            if overridden.contains(field) {
+                tx.execute(&format!("UPDATE rules SET {} = ?2 WHERE id = ?1", field), (rule_id, value))?;
+            }
+        }
+    }
+    tx.execute("DELETE FROM rule_overrides WHERE rule_id = ?1", [rule_id])?;
+    tx.commit()
+}
+
+/// Every column a seed upgrade may change, as stored in the database.
+pub fn fields(rule: &Rule) -> Vec<(&'static str, Value)> {
+    let text = |value: &Option<String>| value.clone().map_or(Value::Null, Value::Text);
+    let int = |value: Option<i64>| value.map_or(Value::Null, Value::Integer);
+    vec![
+        ("title", Value::Text(rule.title.clone())),
+        ("description", Value::Text(rule.description.clone())),
+        ("category", Value::Text(rule.category.clone())),
+        ("risk", Value::Text(rule.risk.as_str().to_string())),
+        ("default_checked", Value::Integer(rule.default_checked as i64)),
+        ("requires_admin", Value::Integer(rule.requires_admin as i64)),
+        ("rule_type", Value::Text(rule.rule_type.as_str().to_string())),
+        ("scope", Value::Text(rule.scope.as_str().to_string())),
+        ("path", text(&rule.path)),
+        ("pattern", text(&rule.pattern)),
+        ("size_threshold_mb", int(rule.size_threshold_mb)),
+        ("age_threshold_days", int(rule.age_threshold_days)),
+        ("action", Value::Text(rule.action.as_str().to_string())),
+        ("tool_cmd", text(&rule.tool_cmd)),
+        ("enabled", Value::Integer(rule.enabled as i64)),
+        ("sort_order", Value::Integer(rule.sort_order)),
+        ("notes", text(&rule.notes)),
+    ]
+}
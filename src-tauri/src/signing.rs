@@ -0,0 +1,74 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rusqlite::Connection;
+
+use crate::error::{AppError, AppResult};
+use crate::settings;
+
+/// Public keys whose pack signatures are trusted, one per line as
+/// `<base64 key> [name]`.
+pub const TRUSTED_KEYS_SETTING: &str = "trusted_pack_keys";
+
+#[derive(Debug, Clone)]
+pub struct TrustedKey {
+    pub name: String,
+    key: VerifyingKey,
+}
+
+/// Reads the trusted keys setting, skipping lines that are not valid keys.
+pub fn load_trusted_keys(conn: &Connection) -> rusqlite::Result<Vec<TrustedKey>> {
+    let value = settings::get_setting(conn, TRUSTED_KEYS_SETTING)?.unwrap_or_default();
+    Ok(parse_trusted_keys(&value))
+}
+
+pub fn parse_trusted_keys(value: &str) -> Vec<TrustedKey> {
+    value
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let key = parts.next()?;
+            let key = VerifyingKey::from_bytes(&decode_array(key)?).ok()?;
+            let name = parts.collect::<Vec<_>>().join(" ");
+            Some(TrustedKey {
+                name: if name.is_empty() { fingerprint(&key) } else { name },
+                key,
+            })
+        })
+        .collect()
+}
+
+/// Checks a detached base64 signature over the exact pack text. Returns the
+/// trusted key that made it, or `None` if no trusted key did.
+pub fn verify<'a>(contents: &str, signature: &str, trusted: &'a [TrustedKey]) -> AppResult<Option<&'a TrustedKey>> {
+    let signature = decode_array(signature.trim())
+        .map(|bytes| Signature::from_bytes(&bytes))
+        .ok_or_else(|| AppError::InvalidInput("Pack signature is not a base64 ed25519 signature".to_string()))?;
+    Ok(trusted
+        .iter()
+        .find(|trusted| trusted.key.verify(contents.as_bytes(), &signature).is_ok()))
+}
+
+/// Signs pack text with a base64 32-byte secret key, for pack maintainers.
+pub fn sign(contents: &str, secret_key: &str) -> AppResult<String> {
+    let secret = decode_array(secret_key.trim())
+        .ok_or_else(|| AppError::InvalidInput("Signing key is not a base64 32-byte ed25519 key".to_string()))?;
+    let signature = SigningKey::from_bytes(&secret).sign(contents.as_bytes());
+    Ok(STANDARD.encode(signature.to_bytes()))
+}
+
+/// The base64 public key matching a base64 secret key, to add to the
+/// trusted keys of other machines.
+pub fn public_key(secret_key: &str) -> AppResult<String> {
+    let secret = decode_array(secret_key.trim())
+        .ok_or_else(|| AppError::InvalidInput("Signing key is not a base64 32-byte ed25519 key".to_string()))?;
+    Ok(STANDARD.encode(SigningKey::from_bytes(&secret).verifying_key().to_bytes()))
+}
+
+fn fingerprint(key: &VerifyingKey) -> String {
+    STANDARD.encode(&key.to_bytes()[..8])
+}
+
+fn decode_array<const N: usize>(text: &str) -> Option<[u8; N]> {
+    STANDARD.decode(text).ok()?.try_into().ok()
+}
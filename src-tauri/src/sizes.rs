@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::Path;
+
+/// `(device, inode)` of a file, or `(volume serial, file index)` on Windows,
+/// used to spot hardlinks.
+pub type FileId = (u64, u64);
+
+/// What a single file costs on disk.
+#[derive(Debug, Clone, Copy)]
+pub struct FileSize {
+    pub logical: u64,
+    /// Bytes actually allocated (`st_blocks * 512` on Unix, the compressed
+    /// size on Windows).
+    pub allocated: u64,
+    pub id: Option<FileId>,
+    pub links: u64,
+}
+
+#[cfg(unix)]
+pub fn file_size(_path: &Path, meta: &Metadata) -> FileSize {
+    use std::os::unix::fs::MetadataExt;
+
+    FileSize {
+        logical: meta.len(),
+        allocated: meta.blocks() * 512,
+        id: Some((meta.dev(), meta.ino())),
+        links: meta.nlink().max(1),
+    }
+}
+
+impl FileSize {
+    /// True when other hardlinks to the same file may exist.
+    pub fn is_linked(&self) -> bool {
+        self.id.is_some() && self.links > 1
+    }
+}
+
+/// Link identity comes from a handle opened for attributes only, as
+/// `fs::metadata` does, and the allocation from `GetCompressedFileSizeW`,
+/// which accounts for compressed and sparse files. Whatever cannot be read
+/// falls back to the logical size and treats the path as its own file.
+#[cfg(windows)]
+pub fn file_size(path: &Path, meta: &Metadata) -> FileSize {
+    use std::fs::OpenOptions;
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+
+    use windows_sys::Win32::Foundation::{GetLastError, NO_ERROR};
+    use windows_sys::Win32::Storage::FileSystem::{
+        GetCompressedFileSizeW, GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION, FILE_FLAG_OPEN_REPARSE_POINT,
+        INVALID_FILE_SIZE,
+    };
+
+    let mut size = FileSize {
+        logical: meta.len(),
+        allocated: meta.len(),
+        id: None,
+        links: 1,
+    };
+    let file = OpenOptions::new()
+        .access_mode(0)
+        .custom_flags(FILE_FLAG_OPEN_REPARSE_POINT)
+        .open(path);
+    if let Ok(file) = file {
+        // SAFETY: the handle is open for the duration of the call and `info`
+        // is plain data the call fills in.
+        let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+        if unsafe { GetFileInformationByHandle(file.as_raw_handle(), &mut info) } != 0 {
+            let index = (u64::from(info.nFileIndexHigh) << 32) | u64::from(info.nFileIndexLow);
+            size.id = Some((u64::from(info.dwVolumeSerialNumber), index));
+            size.links = u64::from(info.nNumberOfLinks).max(1);
+        }
+    }
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut high = 0u32;
+    // SAFETY: `wide` is NUL-terminated and outlives the call.
+    let low = unsafe { GetCompressedFileSizeW(wide.as_ptr(), &mut high) };
+    if low != INVALID_FILE_SIZE || unsafe { GetLastError() } == NO_ERROR {
+        size.allocated = (u64::from(high) << 32) | u64::from(low);
+    }
+    size
+}
+
+/// Bytes a file adds to a total once hardlinks are accounted for.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Counted {
+    pub logical: u64,
+    pub allocated: u64,
+    pub reclaimable: u64,
+}
+
+/// Deduplicates files by `(device, inode)` across everything it is fed.
+///
+/// A file's logical and allocated bytes count the first time any of its links
+/// is seen. Its allocated bytes only become reclaimable once every link has
+/// been seen, because removing some links of a file frees nothing.
+#[derive(Debug, Default)]
+pub struct SizeTally {
+    links_seen: HashMap<FileId, u64>,
+}
+
+impl SizeTally {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, size: &FileSize) -> Counted {
+        let id = match size.id {
+            Some(id) if size.is_linked() => id,
+            _ => {
+                return Counted {
+                    logical: size.logical,
+                    allocated: size.allocated,
+                    reclaimable: size.allocated,
+                }
+            }
+        };
+        let seen = self.links_seen.entry(id).or_insert(0);
+        *seen += 1;
+        Counted {
+            logical: if *seen == 1 { size.logical } else { 0 },
+            allocated: if *seen == 1 { size.allocated } else { 0 },
+            reclaimable: if *seen == size.links { size.allocated } else { 0 },
+        }
+    }
+}
@@ -0,0 +1,484 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+use serde_json::{Map, Value};
+
+use crate::error::{AppError, AppResult};
+use crate::models::{
+    Action, EffectiveRule, PackFormat, Rule, RuleOrigin, RuleOverrides, RuleSource, RuleSourcesView,
+    SourceProblem,
+};
+use crate::packs;
+use crate::rules;
+use crate::seed;
+use crate::signing::{self, TrustedKey};
+use crate::tags;
+
+/// Directories read on top of `rules.db`.
+///
+/// Sources are merged in a fixed order, each taking precedence over the ones
+/// before it: the built-in seed, machine-wide packs, the user's packs and
+/// rules, then per-rule overrides. A later layer can replace or tune a rule
+/// but never remove it.
+#[derive(Debug, Clone, Default)]
+pub struct SourceDirs {
+    /// Packs shipped by an administrator for every user of the machine.
+    pub system: Option<PathBuf>,
+    /// Packs dropped in by the user.
+    pub user: Option<PathBuf>,
+    /// Override files setting individual fields of existing rules.
+    pub overrides: Option<PathBuf>,
+}
+
+impl SourceDirs {
+    pub fn new(data_dir: &Path) -> Self {
+        SourceDirs {
+            system: system_dir(),
+            user: Some(data_dir.join("rules")),
+            overrides: Some(data_dir.join("overrides")),
+        }
+    }
+
+    /// Creates the per-user directories so they are there to drop files in.
+    pub fn create_user_dirs(&self) {
+        for dir in [&self.user, &self.overrides].into_iter().flatten() {
+            let _ = fs::create_dir_all(dir);
+        }
+    }
+
+    pub fn view(&self, problems: Vec<SourceProblem>) -> RuleSourcesView {
+        let display = |dir: &Option<PathBuf>| dir.as_ref().map(|dir| dir.display().to_string());
+        RuleSourcesView {
+            system_dir: display(&self.system),
+            user_dir: display(&self.user),
+            overrides_dir: display(&self.overrides),
+            problems,
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn system_dir() -> Option<PathBuf> {
+    std::env::var_os("ProgramData").map(|dir| PathBuf::from(dir).join("CDiskCleaner").join("rules"))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn system_dir() -> Option<PathBuf> {
+    Some(PathBuf::from("/etc/cdiskcleaner/rules"))
+}
+
+/// Override file the app writes to when a pack rule is tuned in the UI.
+const LOCAL_OVERRIDES: &str = "local.json";
+
+/// Fields an override may set. Override files are not signed, so they can
+/// tune a rule but not change what it removes or runs.
+const OVERRIDABLE: &[&str] = &[
+    "enabled",
+    "default_checked",
+    "size_threshold_mb",
+    "age_threshold_days",
+    "tags",
+    "action",
+];
+
+/// Overridable fields that can make a rule remove more than before.
+const WIDENING: &[&str] = &["size_threshold_mb", "age_threshold_days", "action"];
+
+/// Every rule after merging all sources, plus the files or entries skipped.
+#[derive(Debug, Default)]
+pub struct LoadedRules {
+    pub rules: Vec<EffectiveRule>,
+    pub problems: Vec<SourceProblem>,
+}
+
+/// Merges the database and the pack and override directories.
+///
+/// Seed rules start from their shipped values; fields the user changed in
+/// the app are applied last, as overrides. Packs in the user directory are
+/// held to the same trust rule as imports: `tool_call` and high-risk rules
+/// are skipped unless the file has a `.sig` from a trusted key, and so are
+/// rules replacing a built-in or system rule. The system directory is only
+/// writable by administrators and is trusted as is. Overrides are limited to
+/// `OVERRIDABLE`; see `Merge::apply` for how they are checked.
+pub fn load_rules(conn: &Connection, dirs: &SourceDirs) -> AppResult<LoadedRules> {
+    let stored = rules::list_all_rules(conn)?;
+    let (_, shipped) = seed::load_seed()?;
+    let shipped: HashMap<String, Rule> = shipped.into_iter().map(|rule| (rule.id.clone(), rule)).collect();
+    let mut merge = Merge::default();
+    let mut tuned = Vec::new();
+    let mut user_rules = Vec::new();
+    for rule in stored {
+        if rule.origin != RuleOrigin::Seed {
+            user_rules.push(rule);
+            continue;
+        }
+        // Tags on a seed rule can only have been added by the user.
+        let mut overridden = seed::overrides(conn, &rule.id)?;
+        if !rule.tags.is_empty() {
+            overridden.insert("tags".to_string());
+        }
+        if !overridden.is_empty() {
+            let current = serde_json::to_value(&rule)?;
+            let fields: Map<String, Value> = overridden
+                .into_iter()
+                .filter_map(|field| Some((field.clone(), current.get(&field)?.clone())))
+                .collect();
+            tuned.push((rule.id.clone(), fields));
+        }
+        let base = shipped.get(&rule.id).cloned().unwrap_or(rule);
+        merge.define(base, RuleSource::Builtin);
+    }
+
+    if let Some(dir) = &dirs.system {
+        merge.read_packs(conn, dir, RuleSource::System, None);
+    }
+    if let Some(dir) = &dirs.user {
+        let trusted = signing::load_trusted_keys(conn)?;
+        merge.read_packs(conn, dir, RuleSource::User, Some(&trusted));
+    }
+    for rule in user_rules {
+        // Rules in the database carry no signature.
+        if merge.replaces_trusted(&rule.id) {
+            merge.problem(
+                Path::new("rules.db"),
+                format!("Rule {} needs a trusted signature to replace a built-in or system rule", rule.id),
+            );
+            continue;
+        }
+        merge.define(rule, RuleSource::User);
+    }
+
+    if let Some(dir) = &dirs.overrides {
+        let mut files = source_files(dir, &mut merge.problems);
+        // Changes made in the app win over dropped-in override files.
+        files.sort_by_key(|path| path.file_name() == Some(LOCAL_OVERRIDES.as_ref()));
+        for path in files {
+            let overrides: RuleOverrides = match read_text(&path).and_then(|text| parse(&path, &text)) {
+                Ok(overrides) => overrides,
+                Err(err) => {
+                    merge.problem(&path, err.to_string());
+                    continue;
+                }
+            };
+            for (id, fields) in overrides.rules {
+                if let Err(err) = merge.apply(conn, &id, &fields, RuleSource::Override) {
+                    merge.problem(&path, err.to_string());
+                }
+            }
+        }
+    }
+    for (id, fields) in tuned {
+        if let Err(err) = merge.apply(conn, &id, &fields, RuleSource::Override) {
+            merge.problem(Path::new("rules.db"), err.to_string());
+        }
+    }
+
+    let mut rules = merge.rules;
+    rules.sort_by_key(|effective| effective.rule.sort_order);
+    Ok(LoadedRules {
+        rules,
+        problems: merge.problems,
+    })
+}
+
+/// The effective rules that are enabled, ready to scan or clean.
+pub fn enabled_rules(conn: &Connection, dirs: &SourceDirs) -> AppResult<Vec<Rule>> {
+    Ok(load_rules(conn, dirs)?
+        .rules
+        .into_iter()
+        .map(|effective| effective.rule)
+        .filter(|rule| rule.enabled)
+        .collect())
+}
+
+/// Sets fields of a rule defined by a pack directory.
+///
+/// Pack files are never edited; the fields go to the local override file,
+/// so they survive the pack being updated and show up as overrides.
+pub fn override_pack_rule(conn: &Connection, dirs: &SourceDirs, id: &str, fields: Value) -> AppResult<Rule> {
+    let Value::Object(fields) = fields else {
+        return Err(AppError::Internal("Override fields must be an object".to_string()));
+    };
+    let loaded = load_rules(conn, dirs)?;
+    let effective = loaded
+        .rules
+        .into_iter()
+        .find(|effective| effective.rule.id == id && effective.rule.origin == RuleOrigin::Pack)
+        .ok_or_else(|| AppError::not_found("rule", id))?;
+    let mut merge = Merge::default();
+    merge.index.insert(id.to_string(), 0);
+    merge.rules.push(effective);
+    merge.apply(conn, id, &fields, RuleSource::Override)?;
+    let rule = merge.rules.remove(0).rule;
+
+    let dir = dirs
+        .overrides
+        .as_ref()
+        .ok_or_else(|| AppError::Internal("No overrides directory".to_string()))?;
+    fs::create_dir_all(dir)?;
+    let path = dir.join(LOCAL_OVERRIDES);
+    let mut overrides = match fs::read_to_string(&path) {
+        Ok(text) => parse(&path, &text)?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => RuleOverrides::default(),
+        Err(err) => return Err(err.into()),
+    };
+    overrides.rules.entry(id.to_string()).or_default().extend(fields);
+    fs::write(&path, serde_json::to_string_pretty(&overrides)?)?;
+    Ok(rule)
+}
+
+/// Adds or removes one tag on a rule from any source. Rules stored in the
+/// database keep their tags in `rule_tags`; pack rules get a `tags` override.
+pub fn edit_tag(conn: &Connection, dirs: &SourceDirs, id: &str, tag: &str, add: bool) -> AppResult<Vec<String>> {
+    let tag = tags::normalize(tag);
+    if !tags::valid_tag(&tag) {
+        return Err(AppError::InvalidInput(format!("Invalid tag {:?}", tag)));
+    }
+    let rule = load_rules(conn, dirs)?
+        .rules
+        .into_iter()
+        .map(|effective| effective.rule)
+        .find(|rule| rule.id == id)
+        .ok_or_else(|| AppError::not_found("rule", id))?;
+    let mut tags = rule.tags;
+    if add {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+            tags.sort();
+        }
+    } else {
+        tags.retain(|own| *own != tag);
+    }
+    if rule.origin == RuleOrigin::Pack {
+        override_pack_rule(conn, dirs, id, serde_json::json!({ "tags": tags }))?;
+    } else {
+        rules::set_rule_tags(conn, id, &tags)?;
+    }
+    Ok(tags)
+}
+
+#[derive(Default)]
+struct Merge {
+    rules: Vec<EffectiveRule>,
+    index: HashMap<String, usize>,
+    problems: Vec<SourceProblem>,
+}
+
+impl Merge {
+    /// Adds a full rule definition. Redefining an existing rule replaces its
+    /// fields but keeps its place in the list, whether it is enabled and its
+    /// origin, which says where the app stores changes to it.
+    fn define(&mut self, mut rule: Rule, source: RuleSource) {
+        if let Some(&index) = self.index.get(&rule.id) {
+            let current = &mut self.rules[index];
+            rule.enabled = current.rule.enabled;
+            rule.sort_order = current.rule.sort_order;
+            rule.origin = current.rule.origin;
+            for (field, field_source) in current.field_sources.iter_mut() {
+                if field != "enabled" && field != "sort_order" {
+                    *field_source = source;
+                }
+            }
+            current.rule = rule;
+            current.source = source;
+            return;
+        }
+        let field_sources = seed::fields(&rule)
+            .into_iter()
+            .map(|(field, _)| field)
+            .chain(["tags"])
+            .map(|field| (field.to_string(), source))
+            .collect();
+        self.index.insert(rule.id.clone(), self.rules.len());
+        self.rules.push(EffectiveRule {
+            rule,
+            source,
+            field_sources,
+        });
+    }
+
+    /// Sets tunable fields of an existing rule.
+    ///
+    /// Overrides that can widen what the rule removes (thresholds and the
+    /// action) put the rule through the same checks as a new rule, and none
+    /// may make it need a signature it did not already need. A rejected
+    /// override is reported, but its `enabled = false` still applies.
+    fn apply(&mut self, conn: &Connection, id: &str, fields: &Map<String, Value>, source: RuleSource) -> AppResult<()> {
+        let index = *self
+            .index
+            .get(id)
+            .ok_or_else(|| AppError::InvalidInput(format!("Unknown rule: {}", id)))?;
+        let current = &mut self.rules[index];
+        match overridden(conn, &current.rule, fields) {
+            Ok(rule) => {
+                current.rule = rule;
+                for field in fields.keys() {
+                    current.field_sources.insert(field.clone(), source);
+                }
+                Ok(())
+            }
+            Err(err) => {
+                if fields.get("enabled") == Some(&Value::Bool(false)) {
+                    current.rule.enabled = false;
+                    current.field_sources.insert("enabled".to_string(), source);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    fn read_packs(&mut self, conn: &Connection, dir: &Path, source: RuleSource, trusted: Option<&[TrustedKey]>) {
+        for path in source_files(dir, &mut self.problems) {
+            let text = match read_text(&path) {
+                Ok(text) => text,
+                Err(err) => {
+                    self.problem(&path, err.to_string());
+                    continue;
+                }
+            };
+            let pack = match packs::read_pack(&text, format_of(&path)) {
+                Ok(pack) => pack,
+                Err(err) => {
+                    self.problem(&path, err.to_string());
+                    continue;
+                }
+            };
+            let signed = match trusted {
+                None => true,
+                Some(trusted) => self.is_signed(&path, &text, trusted),
+            };
+            for entry in pack.rules {
+                if !packs::valid_id(&entry.id) {
+                    self.problem(&path, format!("Invalid rule id: {:?}", entry.id));
+                    continue;
+                }
+                if !signed && packs::needs_signature(entry.action, entry.risk) {
+                    self.problem(
+                        &path,
+                        format!("Rule {} needs a trusted signature to run tools or clean high-risk locations", entry.id),
+                    );
+                    continue;
+                }
+                if !signed && self.replaces_trusted(&entry.id) {
+                    self.problem(
+                        &path,
+                        format!("Rule {} needs a trusted signature to replace a built-in or system rule", entry.id),
+                    );
+                    continue;
+                }
+                let sort_order = self.rules.iter().map(|effective| effective.rule.sort_order).max().unwrap_or(0) + 10;
+                let rule = packs::rule_from_pack(entry, RuleOrigin::Pack, true, sort_order);
+                if let Err(err) = rules::validate_rule(conn, &rule) {
+                    self.problem(&path, err.to_string());
+                    continue;
+                }
+                self.define(rule, source);
+            }
+        }
+    }
+
+    /// Whether `id` is already defined by a source other than the user's.
+    fn replaces_trusted(&self, id: &str) -> bool {
+        self.index
+            .get(id)
+            .is_some_and(|&index| self.rules[index].source != RuleSource::User)
+    }
+
+    /// Whether `<pack>.sig` next to the pack verifies against a trusted key.
+    fn is_signed(&mut self, path: &Path, text: &str, trusted: &[TrustedKey]) -> bool {
+        let mut sig_path = path.as_os_str().to_owned();
+        sig_path.push(".sig");
+        let Ok(signature) = fs::read_to_string(PathBuf::from(sig_path)) else {
+            return false;
+        };
+        match signing::verify(text, &signature, trusted) {
+            Ok(signer) => signer.is_some(),
+            Err(err) => {
+                self.problem(path, err.to_string());
+                false
+            }
+        }
+    }
+
+    fn problem(&mut self, path: &Path, message: String) {
+        self.problems.push(SourceProblem {
+            path: path.display().to_string(),
+            message,
+        });
+    }
+}
+
+/// `rule` with override `fields` set, checked as described on `Merge::apply`.
+fn overridden(conn: &Connection, rule: &Rule, fields: &Map<String, Value>) -> AppResult<Rule> {
+    let invalid = |message: String| AppError::InvalidInput(format!("Rule {}: {}", rule.id, message));
+    let mut value = serde_json::to_value(rule)?;
+    for (field, field_value) in fields {
+        if !OVERRIDABLE.contains(&field.as_str()) {
+            return Err(invalid(format!("{} cannot be overridden", field)));
+        }
+        value[field.as_str()] = field_value.clone();
+    }
+    let mut tuned: Rule = serde_json::from_value(value).map_err(|err| invalid(err.to_string()))?;
+    tuned.tags = tuned.tags.iter().map(|tag| tags::normalize(tag)).collect();
+    if fields.contains_key("action") && !matches!(tuned.action, Action::Delete | Action::Recycle | Action::Quarantine) {
+        return Err(invalid("action can only be overridden to delete, recycle or quarantine".to_string()));
+    }
+    if packs::needs_signature(tuned.action, tuned.risk) && !packs::needs_signature(rule.action, rule.risk) {
+        return Err(invalid("overrides cannot make a rule run tools or clean high-risk locations".to_string()));
+    }
+    if WIDENING.iter().any(|field| fields.contains_key(*field)) {
+        rules::validate_rule(conn, &tuned)?;
+    } else if let Some(tag) = tuned.tags.iter().find(|tag| !tags::valid_tag(tag)) {
+        return Err(invalid(format!("invalid tag {:?}", tag)));
+    }
+    Ok(tuned)
+}
+
+/// `.toml` and `.json` files of a directory in name order. A missing
+/// directory is the same as an empty one.
+fn source_files(dir: &Path, problems: &mut Vec<SourceProblem>) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(err) => {
+            problems.push(SourceProblem {
+                path: dir.display().to_string(),
+                message: err.to_string(),
+            });
+            return Vec::new();
+        }
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && format_of(path).is_some())
+        .collect();
+    files.sort();
+    files
+}
+
+fn format_of(path: &Path) -> Option<PackFormat> {
+    path.extension()?.to_str()?.to_ascii_lowercase().parse().ok()
+}
+
+fn read_text(path: &Path) -> AppResult<String> {
+    Ok(fs::read_to_string(path)?)
+}
+
+fn parse(path: &Path, text: &str) -> AppResult<RuleOverrides> {
+    let invalid = |message: String| AppError::InvalidInput(format!("Invalid override file: {}", message));
+    match format_of(path) {
+        Some(PackFormat::Json) => serde_json::from_str(text).map_err(|err| invalid(err.to_string())),
+        _ => toml::from_str(text).map_err(|err| invalid(err.to_string())),
+    }
+}
+
+/// Fields set by the override layer, sorted.
+pub fn overridden_fields(field_sources: &BTreeMap<String, RuleSource>) -> Vec<String> {
+    field_sources
+        .iter()
+        .filter(|(_, source)| **source == RuleSource::Override)
+        .map(|(field, _)| field.clone())
+        .collect()
+}
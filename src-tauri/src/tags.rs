@@ -0,0 +1,362 @@
+use thiserror::Error;
+
+use crate::error::{AppError, AppResult};
+use crate::models::Rule;
+
+/// Deepest nesting of `(` and `!` a tag expression may use.
+const MAX_DEPTH: usize = 32;
+/// Most tokens a tag expression may have; `matches` recurses once per
+/// operand, so long `a | b | ...` chains are bounded too.
+const MAX_TOKENS: usize = 256;
+
+/// A boolean expression over rule tags, such as `browser & !high-risk`.
+///
+/// `!` binds tightest, then `&`, then `|`; parentheses group. Besides its
+/// own tags every rule matches its category, `<risk>-risk` and, if checked
+/// by default, `default`, so `cache & !high-risk` works on untagged
+/// built-in rules too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagExpr {
+    Tag(String),
+    Not(Box<TagExpr>),
+    And(Box<TagExpr>, Box<TagExpr>),
+    Or(Box<TagExpr>, Box<TagExpr>),
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TagExprError {
+    #[error("Tag expression is empty")]
+    Empty,
+    #[error("Unexpected {found:?} at position {position} in tag expression")]
+    Unexpected { found: String, position: usize },
+    #[error("Tag expression ends unexpectedly")]
+    UnexpectedEnd,
+    #[error("Tag expression nests deeper than {MAX_DEPTH} levels")]
+    TooDeep,
+    #[error("Tag expression has more than {MAX_TOKENS} tokens")]
+    TooLong,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Tag(String),
+    Not,
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+impl TagExpr {
+    pub fn parse(text: &str) -> Result<TagExpr, TagExprError> {
+        let tokens = tokenize(text)?;
+        if tokens.is_empty() {
+            return Err(TagExprError::Empty);
+        }
+        if tokens.len() > MAX_TOKENS {
+            return Err(TagExprError::TooLong);
+        }
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            depth: 0,
+        };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some((position, token)) => Err(unexpected(token, *position)),
+        }
+    }
+
+    pub fn matches(&self, rule: &Rule) -> bool {
+        match self {
+            TagExpr::Tag(tag) => has_tag(rule, tag),
+            TagExpr::Not(inner) => !inner.matches(rule),
+            TagExpr::And(left, right) => left.matches(rule) && right.matches(rule),
+            TagExpr::Or(left, right) => left.matches(rule) || right.matches(rule),
+        }
+    }
+}
+
+/// Picks rule ids by an explicit list, a tag expression, or both (the
+/// expression then narrows the list).
+pub fn select(rules: &[Rule], selected_ids: Option<Vec<String>>, expr: Option<&str>) -> AppResult<Vec<String>> {
+    let expr = match expr {
+        Some(expr) => Some(TagExpr::parse(expr).map_err(|err| AppError::InvalidInput(err.to_string()))?),
+        None => None,
+    };
+    match (selected_ids, expr) {
+        (None, None) => Err(AppError::InvalidInput(
+            "Either selected_ids or a tag expression is required".to_string(),
+        )),
+        (Some(ids), None) => Ok(ids),
+        (ids, Some(expr)) => Ok(rules
+            .iter()
+            .filter(|rule| ids.as_ref().is_none_or(|ids| ids.contains(&rule.id)))
+            .filter(|rule| expr.matches(rule))
+            .map(|rule| rule.id.clone())
+            .collect()),
+    }
+}
+
+/// Trims and lowercases a tag; tags are compared case-insensitively.
+pub fn normalize(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+/// Tags are lowercase letters, digits, `_`, `-`, `.` and `:`, starting with
+/// a letter or digit.
+pub fn valid_tag(tag: &str) -> bool {
+    tag.len() <= 32
+        && tag.chars().next().is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && tag.chars().all(is_tag_char)
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-' | '.' | ':')
+}
+
+fn has_tag(rule: &Rule, tag: &str) -> bool {
+    rule.tags.iter().any(|own| own == tag)
+        || rule.category.eq_ignore_ascii_case(tag)
+        || tag.strip_suffix("-risk") == Some(rule.risk.as_str())
+        || (tag == "default" && rule.default_checked)
+}
+
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, TagExprError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '!' => Token::Not,
+            '&' => Token::And,
+            '|' => Token::Or,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            c if is_tag_char(c.to_ascii_lowercase()) => {
+                let mut tag = c.to_ascii_lowercase().to_string();
+                while let Some(&(_, next)) = chars.peek() {
+                    if !is_tag_char(next.to_ascii_lowercase()) {
+                        break;
+                    }
+                    tag.push(next.to_ascii_lowercase());
+                    chars.next();
+                }
+                Token::Tag(tag)
+            }
+            other => {
+                return Err(TagExprError::Unexpected {
+                    found: other.to_string(),
+                    position,
+                })
+            }
+        };
+        tokens.push((position, token));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    /// Open `(` and `!` around the current position.
+    depth: usize,
+}
+
+impl Parser {
+    fn or(&mut self) -> Result<TagExpr, TagExprError> {
+        let mut expr = self.and()?;
+        while self.eat(&Token::Or) {
+            expr = TagExpr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<TagExpr, TagExprError> {
+        let mut expr = self.unary()?;
+        while self.eat(&Token::And) {
+            expr = TagExpr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<TagExpr, TagExprError> {
+        let (position, token) = self.tokens.get(self.pos).cloned().ok_or(TagExprError::UnexpectedEnd)?;
+        self.pos += 1;
+        match token {
+            Token::Tag(tag) => Ok(TagExpr::Tag(tag)),
+            Token::Not => self.nested(|parser| Ok(TagExpr::Not(Box::new(parser.unary()?)))),
+            Token::Open => self.nested(|parser| {
+                let expr = parser.or()?;
+                if parser.eat(&Token::Close) {
+                    Ok(expr)
+                } else {
+                    match parser.tokens.get(parser.pos) {
+                        Some((position, token)) => Err(unexpected(token, *position)),
+                        None => Err(TagExprError::UnexpectedEnd),
+                    }
+                }
+            }),
+            other => Err(unexpected(&other, position)),
+        }
+    }
+
+    fn nested<F>(&mut self, parse: F) -> Result<TagExpr, TagExprError>
+    where
+        F: FnOnce(&mut Parser) -> Result<TagExpr, TagExprError>,
+    {
+        if self.depth >= MAX_DEPTH {
+            return Err(TagExprError::TooDeep);
+        }
+        self.depth += 1;
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        if self.tokens.get(self.pos).map(|(_, token)| token) == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn unexpected(token: &Token, position: usize) -> TagExprError {
+    let found = match token {
+        Token::Tag(tag) => tag.clone(),
+        Token::Not => "!".to_string(),
+        Token::And => "&".to_string(),
+        Token::Or => "|".to_string(),
+        Token::Open => "(".to_string(),
+        Token::Close => ")".to_string(),
+    };
+    TagExprError::Unexpected { found, position }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Action, Risk, RuleOrigin, RuleType, Scope};
+
+    fn rule(tags: &[&str], category: &str, risk: Risk, default_checked: bool) -> Rule {
+        Rule {
+            id: "test".to_string(),
+            title: "Test".to_string(),
+            description: "Test rule".to_string(),
+            category: category.to_string(),
+            risk,
+            default_checked,
+            requires_admin: false,
+            rule_type: RuleType::Path,
+            scope: Scope::User,
+            path: None,
+            pattern: None,
+            size_threshold_mb: None,
+            age_threshold_days: None,
+            action: Action::Delete,
+            tool_cmd: None,
+            enabled: true,
+            sort_order: 0,
+            notes: None,
+            origin: RuleOrigin::User,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+
+    fn tag(name: &str) -> Box<TagExpr> {
+        Box::new(TagExpr::Tag(name.to_string()))
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and_than_or() {
+        assert_eq!(
+            TagExpr::parse("a | b & !c").unwrap(),
+            TagExpr::Or(tag("a"), Box::new(TagExpr::And(tag("b"), Box::new(TagExpr::Not(tag("c")))))),
+        );
+    }
+
+    #[test]
+    fn parentheses_group() {
+        assert_eq!(
+            TagExpr::parse("(a | b) & c").unwrap(),
+            TagExpr::And(Box::new(TagExpr::Or(tag("a"), tag("b"))), tag("c")),
+        );
+        assert_eq!(
+            TagExpr::parse("!(a & b)").unwrap(),
+            TagExpr::Not(Box::new(TagExpr::And(tag("a"), tag("b")))),
+        );
+    }
+
+    #[test]
+    fn tags_are_lowercased() {
+        assert_eq!(TagExpr::parse("  Browser ").unwrap(), TagExpr::Tag("browser".to_string()));
+    }
+
+    #[test]
+    fn rejects_empty_expressions() {
+        assert_eq!(TagExpr::parse(""), Err(TagExprError::Empty));
+        assert_eq!(TagExpr::parse("   "), Err(TagExprError::Empty));
+        assert_eq!(TagExpr::parse("()"), Err(TagExprError::Unexpected { found: ")".to_string(), position: 1 }));
+    }
+
+    #[test]
+    fn rejects_dangling_operators() {
+        assert_eq!(TagExpr::parse("a &"), Err(TagExprError::UnexpectedEnd));
+        assert_eq!(TagExpr::parse("!"), Err(TagExprError::UnexpectedEnd));
+        assert_eq!(TagExpr::parse("| a"), Err(TagExprError::Unexpected { found: "|".to_string(), position: 0 }));
+        assert_eq!(TagExpr::parse("a b"), Err(TagExprError::Unexpected { found: "b".to_string(), position: 2 }));
+        assert_eq!(TagExpr::parse("(a"), Err(TagExprError::UnexpectedEnd));
+        assert_eq!(TagExpr::parse("a)"), Err(TagExprError::Unexpected { found: ")".to_string(), position: 1 }));
+        assert_eq!(TagExpr::parse("a # b"), Err(TagExprError::Unexpected { found: "#".to_string(), position: 2 }));
+    }
+
+    #[test]
+    fn limits_nesting_and_length() {
+        let nested = format!("{}a{}", "(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert!(TagExpr::parse(&nested).is_ok());
+        let too_nested = format!("{}a{}", "(".repeat(MAX_DEPTH + 1), ")".repeat(MAX_DEPTH + 1));
+        assert_eq!(TagExpr::parse(&too_nested), Err(TagExprError::TooDeep));
+        assert_eq!(TagExpr::parse(&format!("{}a", "!".repeat(MAX_DEPTH + 1))), Err(TagExprError::TooDeep));
+        assert_eq!(TagExpr::parse(&"(".repeat(100_000)), Err(TagExprError::TooLong));
+        assert_eq!(TagExpr::parse(&vec!["a"; MAX_TOKENS].join("|")), Err(TagExprError::TooLong));
+    }
+
+    #[test]
+    fn matches_own_tags() {
+        let expr = TagExpr::parse("browser & !high-risk").unwrap();
+        assert!(expr.matches(&rule(&["browser"], "cache", Risk::Low, false)));
+        assert!(!expr.matches(&rule(&["browser"], "cache", Risk::High, false)));
+        assert!(!expr.matches(&rule(&["dev"], "cache", Risk::Low, false)));
+    }
+
+    #[test]
+    fn matches_implicit_tags() {
+        let low = rule(&[], "Cache", Risk::Low, true);
+        assert!(TagExpr::parse("cache").unwrap().matches(&low));
+        assert!(TagExpr::parse("low-risk").unwrap().matches(&low));
+        assert!(!TagExpr::parse("medium-risk").unwrap().matches(&low));
+        assert!(TagExpr::parse("default").unwrap().matches(&low));
+        assert!(!TagExpr::parse("default").unwrap().matches(&rule(&[], "cache", Risk::Low, false)));
+        assert!(!TagExpr::parse("risk").unwrap().matches(&low));
+    }
+
+    #[test]
+    fn select_narrows_ids_by_expression() {
+        let mut browser = rule(&["browser"], "cache", Risk::Low, true);
+        browser.id = "browser".to_string();
+        let mut system = rule(&[], "system", Risk::High, false);
+        system.id = "system".to_string();
+        let rules = [browser, system];
+        assert_eq!(select(&rules, None, Some("!high-risk")).unwrap(), vec!["browser"]);
+        assert_eq!(
+            select(&rules, Some(vec!["system".to_string()]), Some("cache | system")).unwrap(),
+            vec!["system"]
+        );
+        assert!(select(&rules, None, None).is_err());
+        assert!(select(&rules, None, Some("a &")).is_err());
+    }
+}
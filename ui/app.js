@@ -16,9 +16,11 @@ const rulesList = document.getElementById("rulesList");
 const ruleCount = document.getElementById("ruleCount");
 const selectedCount = document.getElementById("selectedCount");
 const estSize = document.getElementById("estSize");
+const lifetimeSize = document.getElementById("lifetimeSize");
 const riskChips = document.querySelectorAll(".chip");
 const scanBtn = document.getElementById("scanBtn");
 const cleanBtn = document.getElementById("cleanBtn");
+const profileSelect = document.getElementById("profileSelect");
 const analysisModal = document.getElementById("analysisModal");
 const analysisClose = document.getElementById("analysisClose");
 const analysisTotal = document.getElementById("analysisTotal");
@@ -28,6 +30,14 @@ const analysisByDrive = document.getElementById("analysisByDrive");
 const analysisItems = document.getElementById("analysisItems");
 const analysisToggle = document.getElementById("analysisToggle");
 const scanStatus = document.getElementById("scanStatus");
+const previewModal = document.getElementById("previewModal");
+const previewClose = document.getElementById("previewClose");
+const previewTitle = document.getElementById("previewTitle");
+const previewSort = document.getElementById("previewSort");
+const previewMeta = document.getElementById("previewMeta");
+const previewPrev = document.getElementById("previewPrev");
+const previewNext = document.getElementById("previewNext");
+const previewItems = document.getElementById("previewItems");
 const titlebarMin = document.getElementById("titlebar-minimize");
 const titlebarMax = document.getElementById("titlebar-maximize");
 const titlebarClose = document.getElementById("titlebar-close");
@@ -36,8 +46,11 @@ let rules = [];
 let riskFilter = "all";
 let scanResults = new Map();
 let showAnalysis = true;
-let scanInProgress = false;
+let scanJobId = null;
 let scanCancelRequested = false;
+let cleanJobId = null;
+const jobWaiters = new Map();
+let previewState = { ruleId: null, sessionId: null, jobId: null, page: 0, pageSize: 100, totalFiles: 0 };
 
 const RULE_I18N = {
   sys_temp: { title: "系统临时文件", description: "Windows 系统临时目录", category: "临时文件" },
@@ -106,6 +119,21 @@ const RISK_LABELS = {
   high: "高"
 };
 
+const SOURCE_LABELS = {
+  builtin: "内置",
+  system: "系统规则包",
+  user: "用户规则",
+  override: "覆盖"
+};
+
+function sourceText(rule) {
+  const lines = [`来源：${SOURCE_LABELS[rule.source] ?? rule.source}`];
+  if (rule.overridden?.length) {
+    lines.push(`已覆盖：${rule.overridden.join(", ")}`);
+  }
+  return lines.join("\n");
+}
+
 function getRuleDisplay(rule) {
   const i18n = RULE_I18N[rule.id];
   return {
@@ -118,17 +146,37 @@ function getRuleDisplay(rule) {
 
 const STATUS_LABELS = {
   blocked: "需要管理员",
+  invalid: "规则无效",
+  protected: "受保护路径",
   missing: "不存在",
   missing_path: "路径缺失",
   unsupported: "不支持",
+  cancelled: "已取消",
+  partial: "部分完成",
+  skipped: "已跳过",
+  error: "出错"
+};
+
+// Messages for `AppError` codes; anything else falls back to the backend text.
+const ERROR_MESSAGES = {
+  db_locked: "数据库被占用，请稍后重试",
+  requires_admin: "需要管理员权限",
+  invalid_rule: "规则无效",
+  permission_denied: "无权限",
+  not_found: "对象不存在",
+  untrusted_pack: "规则包未经受信任的签名，不能包含工具或高风险规则",
   cancelled: "已取消"
 };
 
+function errorText(err) {
+  if (!err) return "";
+  return ERROR_MESSAGES[err.code] ?? err.message ?? String(err);
+}
+
 if (eventApi?.listen && scanStatus) {
   eventApi.listen("scan:progress", (event) => {
-    if (!scanInProgress) return;
     const payload = event?.payload ?? event;
-    if (!payload) return;
+    if (!payload || !scanJobId || payload.job_id !== scanJobId) return;
     const title = payload.title ?? payload.id ?? "";
     const path = payload.path ?? "";
     scanStatus.textContent = path
@@ -137,6 +185,44 @@ if (eventApi?.listen && scanStatus) {
   });
 }
 
+if (eventApi?.listen && scanStatus) {
+  eventApi.listen("cleanup:progress", (event) => {
+    const payload = event?.payload ?? event;
+    if (!payload || !cleanJobId || payload.job_id !== cleanJobId) return;
+    const title = getRuleDisplay(payload).title;
+    const counts = `${payload.files}/${payload.total_files} 个文件 · ${formatBytes(payload.bytes)}`;
+    scanStatus.textContent = payload.path
+      ? `正在清理：${title} · ${counts} · ${payload.path}`
+      : `正在清理：${title} · ${counts}`;
+  });
+}
+
+if (eventApi?.listen) {
+  eventApi.listen("job:finished", (event) => {
+    const job = event?.payload ?? event;
+    const resolve = job && jobWaiters.get(job.id);
+    if (!resolve) return;
+    jobWaiters.delete(job.id);
+    resolve(job);
+  });
+}
+
+// Resolves with the finished job, from the `job:finished` event or, if the job
+// already finished before we started listening, from its current status.
+function waitForJob(jobId) {
+  return new Promise((resolve) => {
+    jobWaiters.set(jobId, resolve);
+    invoke("job_status_cmd", { jobId })
+      .then((job) => {
+        if (job.status !== "running" && jobWaiters.has(jobId)) {
+          jobWaiters.delete(jobId);
+          resolve(job);
+        }
+      })
+      .catch((err) => console.error(err));
+  });
+}
+
 async function toggleMaximize() {
   if (!appWindow) return;
   if (typeof appWindow.isMaximized !== "function") {
@@ -167,39 +253,58 @@ function renderRules() {
   filtered.forEach((rule) => {
     const display = getRuleDisplay(rule);
     const item = document.createElement("div");
-    item.className = `rule-item ${rule.blocked ? "blocked" : ""}`;
+    const unavailable = rule.blocked || Boolean(rule.invalid_reason) || !rule.enabled;
+    item.className = `rule-item ${unavailable ? "blocked" : ""}`;
     item.dataset.ruleId = rule.id;
 
     const checkbox = document.createElement("input");
     checkbox.type = "checkbox";
-    checkbox.checked = rule.default_checked && !rule.blocked;
-    checkbox.disabled = rule.blocked;
+    checkbox.checked = rule.default_checked && !unavailable;
+    checkbox.disabled = unavailable;
     checkbox.addEventListener("change", updateSelectionCount);
 
     const details = document.createElement("div");
     const title = document.createElement("div");
     title.className = "rule-title";
     title.textContent = display.title;
+    title.title = sourceText(rule);
     const desc = document.createElement("div");
     desc.className = "rule-desc";
-    desc.textContent = rule.blocked
-      ? `${display.description} · ${rule.blocked_reason}`
+    desc.textContent = unavailable
+      ? `${display.description} · ${rule.enabled ? rule.invalid_reason ?? rule.blocked_reason : "已停用"}`
       : display.description;
     details.appendChild(title);
     details.appendChild(desc);
+    if (rule.tags?.length) {
+      const tags = document.createElement("div");
+      tags.className = "rule-tags";
+      tags.textContent = rule.tags.map((tag) => `#${tag}`).join(" ");
+      details.appendChild(tags);
+    }
 
     const size = document.createElement("div");
     size.className = "rule-size";
     size.textContent = "--";
+    if (!unavailable && ["path", "pattern", "app_residue"].includes(rule.rule_type)) {
+      size.classList.add("previewable");
+      size.title = "预览将被清理的文件";
+      size.addEventListener("click", () => openPreview(rule));
+    }
 
     const badge = document.createElement("div");
     badge.className = `badge ${rule.risk}`;
     badge.textContent = display.riskLabel;
 
+    const toggle = document.createElement("button");
+    toggle.className = "rule-enable";
+    toggle.textContent = rule.enabled ? "停用" : "启用";
+    toggle.addEventListener("click", () => setRuleEnabled(rule, !rule.enabled));
+
     item.appendChild(checkbox);
     item.appendChild(details);
     item.appendChild(size);
     item.appendChild(badge);
+    item.appendChild(toggle);
     rulesList.appendChild(item);
   });
 
@@ -207,6 +312,17 @@ function renderRules() {
   updateSelectionCount();
 }
 
+async function setRuleEnabled(rule, enabled) {
+  if (!invoke) return;
+  try {
+    await invoke("set_rule_enabled_cmd", { ruleId: rule.id, enabled });
+    await loadRules();
+  } catch (err) {
+    console.error(err);
+    if (scanStatus) scanStatus.textContent = errorText(err);
+  }
+}
+
 function updateSelectionCount() {
   const checked = rulesList.querySelectorAll("input[type='checkbox']:checked").length;
   selectedCount.textContent = checked.toString();
@@ -238,15 +354,51 @@ async function loadRules() {
   renderRules();
 }
 
+async function loadProfiles() {
+  if (!invoke || !profileSelect) return;
+  try {
+    const profiles = await invoke("list_profiles_cmd");
+    profiles.forEach((profile) => {
+      const option = document.createElement("option");
+      option.value = profile.name;
+      option.textContent = profile.name;
+      option.title = profile.description;
+      profileSelect.appendChild(option);
+    });
+  } catch (err) {
+    console.error(err);
+  }
+}
+
+// Space freed by every recorded cleanup so far.
+async function loadLifetime() {
+  if (!invoke || !lifetimeSize) return;
+  try {
+    const stats = await invoke("cleanup_stats_cmd");
+    lifetimeSize.textContent = formatBytes(stats.total_bytes);
+    lifetimeSize.title = `共 ${stats.runs} 次清理 · ${stats.total_files} 个文件`;
+    if (stats.quarantined_bytes) lifetimeSize.title += ` · 隔离区中 ${formatBytes(stats.quarantined_bytes)}`;
+  } catch (err) {
+    console.error(err);
+  }
+}
+
+function isHighRisk(id) {
+  const rule = rules.find((r) => r.id === id);
+  return Boolean(rule && rule.risk === "high");
+}
+
 function updateEstimatedSize() {
   let total = 0;
-  const items = rulesList.querySelectorAll(".rule-item");
-  items.forEach((item) => {
-    const checkbox = item.querySelector("input[type='checkbox']");
-    if (!checkbox || !checkbox.checked) return;
-    const id = item.dataset.ruleId;
+  const selected = new Set(getSelectedRuleIds());
+  selected.forEach((id) => {
     const scan = scanResults.get(id);
-    if (scan) total += scan.total_bytes;
+    if (!scan) return;
+    total += scan.reclaimable_bytes;
+    // Files shared with an unselected rule were attributed to that rule.
+    (scan.overlaps ?? []).forEach((overlap) => {
+      if (!selected.has(overlap.rule_id)) total += overlap.total_bytes;
+    });
   });
   estSize.textContent = formatBytes(total);
 }
@@ -259,41 +411,89 @@ function formatBytes(bytes) {
   return `${mb.toFixed(1)} MB`;
 }
 
+function scanResultLabel(result) {
+  if (result.status === "ok") return formatBytes(result.total_bytes);
+  if (!result.complete && result.file_count) return `≥ ${formatBytes(result.total_bytes)}`;
+  return STATUS_LABELS[result.status] ?? result.status;
+}
+
+function overlapSummary(overlaps) {
+  if (!overlaps || !overlaps.length) return "";
+  return overlaps
+    .map((overlap) => {
+      const owner = rules.find((r) => r.id === overlap.rule_id);
+      const title = owner ? getRuleDisplay(owner).title : overlap.rule_id;
+      return `与「${title}」重叠 ${overlap.file_count} 个文件（${formatBytes(overlap.total_bytes)}）`;
+    })
+    .join("\n");
+}
+
+function refusedSummary(refused) {
+  if (!refused || !refused.count) return "";
+  const samples = refused.samples.map((sample) => sample.path).join("\n");
+  return `受保护而未删除：${refused.count}${samples ? `\n${samples}` : ""}`;
+}
+
+function errorSummary(errors) {
+  if (!errors) return "";
+  const parts = [
+    ["无权限", errors.permission_denied],
+    ["已不存在", errors.not_found],
+    ["被占用", errors.in_use],
+    ["其他", errors.other],
+  ]
+    .filter(([, count]) => count)
+    .map(([label, count]) => `${label} ${count}`);
+  if (!parts.length) return "";
+  const samples = errors.samples.map((sample) => `${sample.kind}: ${sample.path}`).join("\n");
+  return `出错：${parts.join("，")}${samples ? `\n${samples}` : ""}`;
+}
+
 async function scanRules() {
   if (!invoke) return;
-  if (scanInProgress) {
+  if (scanJobId) {
     scanCancelRequested = true;
     scanBtn.textContent = "取消中...";
     if (scanStatus) scanStatus.textContent = "正在取消扫描...";
     try {
-      await invoke("cancel_scan_cmd");
+      await invoke("cancel_job_cmd", { jobId: scanJobId });
     } catch (err) {
       console.error(err);
     }
     return;
   }
-  scanInProgress = true;
   scanCancelRequested = false;
   scanBtn.textContent = "取消扫描";
   cleanBtn.disabled = true;
   if (scanStatus) scanStatus.textContent = "正在扫描...";
   let results = [];
   try {
-    results = await invoke("scan_rules_cmd");
+    scanJobId = await invoke("start_scan_job_cmd");
+    const job = await waitForJob(scanJobId);
+    if (job.status === "failed") throw job.error;
+    results = job.result ?? [];
     scanResults = new Map(results.map((r) => [r.id, r]));
     results.forEach((result) => {
       const item = rulesList.querySelector(`[data-rule-id='${result.id}']`);
       if (!item) return;
       const sizeEl = item.querySelector(".rule-size");
       if (!sizeEl) return;
-      sizeEl.textContent =
-        result.status === "ok" ? formatBytes(result.total_bytes) : (STATUS_LABELS[result.status] ?? result.status);
+      sizeEl.textContent = scanResultLabel(result);
+      sizeEl.title = [
+        `匹配 ${formatBytes(result.total_bytes)} · 占用 ${formatBytes(result.allocated_bytes)} · 可释放 ${formatBytes(
+          result.reclaimable_bytes
+        )}`,
+        overlapSummary(result.overlaps),
+        errorSummary(result.errors),
+      ]
+        .filter(Boolean)
+        .join("\n");
     });
     updateSpaceChart();
   } catch (err) {
     console.error(err);
   } finally {
-    scanInProgress = false;
+    scanJobId = null;
     scanBtn.textContent = "扫描";
     cleanBtn.disabled = false;
     updateEstimatedSize();
@@ -309,7 +509,7 @@ scanBtn.addEventListener("click", scanRules);
 function updateSpaceChart() {
   const categories = {};
   scanResults.forEach((result, id) => {
-    if (result.status !== "ok") return;
+    if (result.status !== "ok" && result.status !== "cancelled") return;
     const rule = rules.find((r) => r.id === id);
     if (!rule) return;
     const display = getRuleDisplay(rule);
@@ -342,31 +542,82 @@ function getSelectedRuleIds() {
 
 async function cleanSelected() {
   if (!invoke) return;
-  const selectedIds = getSelectedRuleIds();
-  if (!selectedIds.length) return;
-  const hasHighRisk = selectedIds.some((id) => {
-    const rule = rules.find((r) => r.id === id);
-    return rule && rule.risk === "high";
-  });
-  if (
-    hasHighRisk &&
-    !confirm(
-      "已选择高风险项目（含注册表/应用残留）。便携应用可能被误判，是否继续清理？"
-    )
-  ) {
+  if (cleanJobId) {
+    cleanBtn.textContent = "取消中...";
+    if (scanStatus) scanStatus.textContent = "正在取消清理...";
+    try {
+      await invoke("cancel_job_cmd", { jobId: cleanJobId });
+    } catch (err) {
+      console.error(err);
+    }
+    return;
+  }
+  const profile = profileSelect?.value || null;
+  const selectedIds = profile ? null : getSelectedRuleIds();
+  if (selectedIds && !selectedIds.length) return;
+  const highRiskPrompt = "已选择高风险项目（含注册表/应用残留）。便携应用可能被误判，是否继续清理？";
+  if (selectedIds && selectedIds.some(isHighRisk) && !confirm(highRiskPrompt)) {
     return;
   }
   cleanBtn.disabled = true;
-  cleanBtn.textContent = "清理中...";
+  cleanBtn.textContent = "准备中...";
   try {
-    const report = await invoke("clean_rules_cmd", { selectedIds });
+    const planJob = await waitForJob(await invoke("start_plan_job_cmd", { profile, selectedIds }));
+    if (planJob.status !== "completed") throw planJob.error;
+    const plan = planJob.result;
+    if (profile && plan.rules.some((rule) => isHighRisk(rule.id)) && !confirm(highRiskPrompt)) {
+      await invoke("discard_plan_cmd", { planId: plan.id });
+      return;
+    }
+    const drift = plan.drift.length
+      ? `\n\n以下规则自上次扫描后匹配的文件已变化：\n${plan.drift
+          .map(
+            (d) =>
+              `${d.rule_id}：扫描 ${d.scanned_files} 个（${formatBytes(d.scanned_bytes)}）→ 现在 ${
+                d.planned_files
+              } 个（${formatBytes(d.planned_bytes)}）`
+          )
+          .join("\n")}`
+      : "";
+    const { unknown, disabled } = plan.unavailable;
+    const unavailable = [
+      unknown.length ? `方案中的规则不存在：${unknown.join("、")}` : "",
+      disabled.length ? `方案中的规则已禁用：${disabled.join("、")}` : "",
+    ]
+      .filter(Boolean)
+      .map((line) => `\n${line}`)
+      .join("");
+    if (
+      !confirm(
+        `将清理 ${plan.total_files} 个文件（${formatBytes(plan.total_bytes)}，预计释放 ${formatBytes(
+          plan.reclaimable_bytes
+        )}），是否继续？${unavailable}${drift}`
+      )
+    ) {
+      await invoke("discard_plan_cmd", { planId: plan.id });
+      return;
+    }
+    cleanBtn.disabled = false;
+    cleanBtn.textContent = "取消清理";
+    scanBtn.disabled = true;
+    cleanJobId = await invoke("start_cleanup_job_cmd", { planId: plan.id, acceptDrift: plan.drift.length > 0 });
+    const job = await waitForJob(cleanJobId);
+    if (job.status === "failed") throw job.error;
+    const report = job.result;
+    const wasCancelled = report.items.some((item) => item.status === "cancelled");
+    if (scanStatus) scanStatus.textContent = wasCancelled ? "已取消清理" : "清理完成";
+    if (scanStatus && report.errors?.length) scanStatus.textContent += `（${report.errors.join("；")}）`;
+    loadLifetime();
     if (showAnalysis) {
       renderAnalysis(report);
       analysisModal.classList.remove("hidden");
     }
   } catch (err) {
     console.error(err);
+    if (scanStatus) scanStatus.textContent = errorText(err);
   } finally {
+    cleanJobId = null;
+    scanBtn.disabled = false;
     cleanBtn.disabled = false;
     cleanBtn.textContent = "清理所选";
   }
@@ -400,14 +651,133 @@ function renderBucketList(container, items) {
 function renderItems(container, items) {
   container.innerHTML = "";
   items.forEach((item) => {
-    if (item.status !== "ok" && item.status !== "partial") return;
+    if (item.status !== "ok" && item.status !== "partial" && !(item.status === "cancelled" && item.file_count)) return;
     const row = document.createElement("div");
     row.className = "bucket-item";
     row.innerHTML = `<span>${item.title}</span><span>${formatBytes(item.total_bytes)}</span>`;
+    const errors = [errorSummary(item.errors), refusedSummary(item.refused)].filter(Boolean).join("\n");
+    if (errors) {
+      row.title = errors;
+      row.classList.add("has-errors");
+    }
     container.appendChild(row);
   });
 }
 
+async function loadPreviewPage() {
+  if (!invoke || !previewState.sessionId) return;
+  previewItems.textContent = "加载中...";
+  try {
+    const page = await invoke("preview_page_cmd", {
+      sessionId: previewState.sessionId,
+      sort: previewSort.value,
+      page: previewState.page,
+      pageSize: previewState.pageSize,
+    });
+    renderPreviewPage(page);
+  } catch (err) {
+    console.error(err);
+    previewItems.textContent = err?.code ? errorText(err) : "预览失败";
+  }
+}
+
+function renderPreviewPage(page) {
+  previewState.totalFiles = page.total_files;
+  const pages = Math.max(1, Math.ceil(page.total_files / page.page_size));
+  previewMeta.textContent = `${page.total_files} 个文件 · ${formatBytes(page.total_bytes)} · 第 ${
+    page.page + 1
+  }/${pages} 页`;
+  const errors = errorSummary(page.errors);
+  previewMeta.title = errors;
+  if (errors) previewMeta.textContent += ` · ${errors.split("\n")[0]}`;
+  previewPrev.disabled = page.page === 0;
+  previewNext.disabled = page.page + 1 >= pages;
+  previewItems.innerHTML = "";
+  if (!page.entries.length) {
+    previewItems.textContent = STATUS_LABELS[page.status] ?? "暂无数据";
+    return;
+  }
+  page.entries.forEach((entry) => {
+    const row = document.createElement("div");
+    row.className = "bucket-item";
+    const path = document.createElement("span");
+    path.className = "preview-path";
+    path.textContent = entry.path;
+    const size = document.createElement("span");
+    size.textContent = formatBytes(entry.size);
+    row.appendChild(path);
+    row.appendChild(size);
+    previewItems.appendChild(row);
+  });
+}
+
+async function openPreview(rule) {
+  closePreviewSession();
+  previewState = { ...previewState, ruleId: rule.id, sessionId: null, jobId: null, page: 0, totalFiles: 0 };
+  previewTitle.textContent = `文件预览 · ${getRuleDisplay(rule).title}`;
+  previewMeta.textContent = "";
+  previewMeta.title = "";
+  previewModal.classList.remove("hidden");
+  if (!invoke) return;
+  previewItems.textContent = "正在读取文件...";
+  try {
+    const jobId = await invoke("start_preview_job_cmd", { ruleId: rule.id });
+    previewState.jobId = jobId;
+    const job = await waitForJob(jobId);
+    if (previewState.jobId !== jobId) {
+      // Closed or replaced while walking; drop the session nobody will page.
+      if (job.status === "completed") {
+        invoke("discard_preview_cmd", { sessionId: job.result.session_id }).catch(console.error);
+      }
+      return;
+    }
+    previewState.jobId = null;
+    if (job.status !== "completed") throw job.error;
+    previewState.sessionId = job.result.session_id;
+    if (previewSort.value === "size") {
+      renderPreviewPage(job.result);
+    } else {
+      loadPreviewPage();
+    }
+  } catch (err) {
+    console.error(err);
+    previewItems.textContent = err?.code ? errorText(err) : "预览失败";
+  }
+}
+
+// Cancels a preview walk still running and drops the cached session.
+function closePreviewSession() {
+  if (!invoke) return;
+  if (previewState.jobId) invoke("cancel_job_cmd", { jobId: previewState.jobId }).catch(console.error);
+  if (previewState.sessionId) {
+    invoke("discard_preview_cmd", { sessionId: previewState.sessionId }).catch(console.error);
+  }
+  previewState.jobId = null;
+  previewState.sessionId = null;
+}
+
+previewSort.addEventListener("change", () => {
+  previewState.page = 0;
+  loadPreviewPage();
+});
+
+previewPrev.addEventListener("click", () => {
+  if (previewState.page === 0) return;
+  previewState.page -= 1;
+  loadPreviewPage();
+});
+
+previewNext.addEventListener("click", () => {
+  previewState.page += 1;
+  loadPreviewPage();
+});
+
+previewClose.addEventListener("click", () => {
+  previewModal.classList.add("hidden");
+  closePreviewSession();
+  previewState.ruleId = null;
+});
+
 analysisClose.addEventListener("click", () => {
   analysisModal.classList.add("hidden");
 });
@@ -431,5 +801,24 @@ async function loadSettings() {
   analysisToggle.checked = showAnalysis;
 }
 
+// Tells the user once what a seed upgrade did to the built-in rules.
+async function showSeedUpgrade() {
+  if (!invoke || !scanStatus) return;
+  try {
+    const upgrade = await invoke("last_seed_upgrade_cmd");
+    if (!upgrade) return;
+    const kept = upgrade.changed.filter((change) => change.kept_overrides.length).length;
+    scanStatus.textContent = `规则库已更新：新增 ${upgrade.added.length} 条，修改 ${upgrade.changed.length} 条，移除 ${
+      upgrade.removed.length
+    } 条${kept ? `（保留了 ${kept} 条规则的自定义设置）` : ""}`;
+    await invoke("dismiss_seed_upgrade_cmd");
+  } catch (err) {
+    console.error(err);
+  }
+}
+
 loadSettings();
 loadRules();
+loadProfiles();
+loadLifetime();
+showSeedUpgrade();